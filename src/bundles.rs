@@ -9,6 +9,8 @@ pub struct Bundle {
     pub name: &'static str,
     pub description: &'static str,
     pub paths: &'static [&'static str],
+    /// Commands run after a restore changes any of this bundle's paths.
+    pub post_restore: &'static [&'static str],
 }
 
 const BUNDLE_DEFINITIONS: &[Bundle] = &[
@@ -23,6 +25,11 @@ const BUNDLE_DEFINITIONS: &[Bundle] = &[
             "~/.config/swayosd",
             "~/.config/wayvnc",
         ],
+        post_restore: &[
+            "hyprctl reload",
+            "omarchy-restart-waybar",
+            "omarchy-restart-swayosd",
+        ],
     },
     Bundle {
         id: "terminals",
@@ -33,6 +40,7 @@ const BUNDLE_DEFINITIONS: &[Bundle] = &[
             "~/.config/ghostty",
             "~/.config/kitty",
         ],
+        post_restore: &[],
     },
     Bundle {
         id: "cli_tools",
@@ -45,30 +53,35 @@ const BUNDLE_DEFINITIONS: &[Bundle] = &[
             "~/.config/cava",
             "~/.config/walker",
         ],
+        post_restore: &[],
     },
     Bundle {
         id: "editors",
         name: "Editors",
         description: "Neovim and Typora settings",
         paths: &["~/.config/nvim", "~/.config/Typora"],
+        post_restore: &[],
     },
     Bundle {
         id: "dev_git",
         name: "Git Tooling",
         description: "git, lazygit, gh configuration",
         paths: &["~/.config/git", "~/.config/lazygit", "~/.config/gh"],
+        post_restore: &[],
     },
     Bundle {
         id: "creative",
         name: "Creative Tools",
         description: "Aether and Elephant assets",
         paths: &["~/.config/aether", "~/.config/elephant"],
+        post_restore: &[],
     },
     Bundle {
         id: "system",
         name: "System Services",
         description: "User-level systemd units",
         paths: &["~/.config/systemd"],
+        post_restore: &["systemctl --user daemon-reload"],
     },
 ];
 
@@ -1,30 +1,274 @@
-use std::{collections::BTreeSet, fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
 use anyhow::{Context, Result};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use toml_edit::DocumentMut;
 
 use crate::bundles;
+use crate::fs_ops::{self, DeployMode};
+use crate::hooks::FailurePolicy;
+use crate::selector::SelectorKind;
+use crate::storage::StorageKind;
+use crate::validate::{self, Problem, Severity};
 
 const CONFIG_DIR_NAME: &str = "omarchy-syncd";
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Alternate config file, used unless `--config` is given.
+pub const CONFIG_ENV: &str = "OMARCHY_SYNCD_CONFIG";
+/// Replaces `repo.url` from the config file.
+pub const REPO_URL_ENV: &str = "OMARCHY_SYNCD_REPO_URL";
+/// Replaces `repo.branch` from the config file.
+pub const BRANCH_ENV: &str = "OMARCHY_SYNCD_BRANCH";
+/// Replaces `ui.selector` from the config file, unless `--selector` is given.
+pub const SELECTOR_ENV: &str = "OMARCHY_SYNCD_SELECTOR";
+
+/// Config file chosen with the global `--config` flag.
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+/// Selector backend chosen with the global `--selector` flag.
+static SELECTOR: OnceLock<SelectorKind> = OnceLock::new();
+
+/// Schema version written to new config files. Files without a `version` key are version 0.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Upgrade steps applied in order: `MIGRATIONS[n]` turns a version `n` file into version `n + 1`.
+/// Each step edits the document in place so comments and formatting survive.
+const MIGRATIONS: &[fn(&mut DocumentMut) -> Result<()>] = &[
+    // Version 0 predates the `version` key; its layout is otherwise identical to version 1.
+    |_| Ok(()),
+];
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct RepoConfig {
+    #[serde(default)]
     pub url: String,
     #[serde(default = "default_branch")]
     pub branch: String,
 }
 
+impl Default for RepoConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            branch: default_branch(),
+        }
+    }
+}
+
+impl RepoConfig {
+    pub fn is_unset(&self) -> bool {
+        self.url.is_empty() && self.branch == default_branch()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct FileConfig {
     pub paths: Vec<String>,
     #[serde(default)]
     pub bundles: Vec<String>,
+    /// How restore puts tracked paths into `$HOME`.
+    #[serde(default, skip_serializing_if = "DeployMode::is_default")]
+    pub mode: DeployMode,
+    /// Per-path exceptions to `mode`, keyed by a tracked path or a directory containing it.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modes: BTreeMap<String, DeployMode>,
+}
+
+impl FileConfig {
+    /// Mode for the tracked path `raw`; the most specific entry in `modes` wins.
+    pub fn mode_for(&self, raw: &str) -> DeployMode {
+        self.modes
+            .iter()
+            .filter(|(key, _)| Path::new(raw).starts_with(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map_or(self.mode, |(_, mode)| *mode)
+    }
+
+    pub fn uses_links(&self) -> bool {
+        self.mode == DeployMode::Link || self.modes.values().any(|mode| *mode == DeployMode::Link)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub kind: StorageKind,
+    /// Target directory for the `directory` and `tarball` storage kinds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Number of tarball snapshots to retain.
+    #[serde(default = "default_keep")]
+    pub keep: usize,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            kind: StorageKind::default(),
+            path: None,
+            keep: default_keep(),
+        }
+    }
+}
+
+impl StorageConfig {
+    pub fn is_default(&self) -> bool {
+        *self == StorageConfig::default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PackagesConfig {
+    /// Record explicitly installed pacman and AUR packages on every backup.
+    #[serde(default = "default_capture_packages")]
+    pub capture: bool,
+    /// AUR helper used by `packages install` (defaults to paru or yay, whichever is found).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aur_helper: Option<String>,
+}
+
+impl Default for PackagesConfig {
+    fn default() -> Self {
+        Self {
+            capture: default_capture_packages(),
+            aur_helper: None,
+        }
+    }
+}
+
+impl PackagesConfig {
+    pub fn is_default(&self) -> bool {
+        *self == PackagesConfig::default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    #[serde(default)]
+    pub pre_backup: Vec<String>,
+    #[serde(default)]
+    pub post_backup: Vec<String>,
+    #[serde(default)]
+    pub pre_restore: Vec<String>,
+    #[serde(default)]
+    pub post_restore: Vec<String>,
+    /// Run each restored bundle's built-in reload actions (e.g. `hyprctl reload`).
+    #[serde(default = "default_bundle_actions")]
+    pub bundle_actions: bool,
+    /// Seconds a single hook may run before it is killed.
+    #[serde(default = "default_hook_timeout")]
+    pub timeout: u64,
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            pre_backup: Vec::new(),
+            post_backup: Vec::new(),
+            pre_restore: Vec::new(),
+            post_restore: Vec::new(),
+            bundle_actions: default_bundle_actions(),
+            timeout: default_hook_timeout(),
+            on_failure: FailurePolicy::default(),
+        }
+    }
+}
+
+impl HooksConfig {
+    pub fn is_default(&self) -> bool {
+        *self == HooksConfig::default()
+    }
+}
+
+/// Which backup/restore outcomes raise a desktop notification.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationsConfig {
+    #[serde(default)]
+    pub on_success: bool,
+    #[serde(default)]
+    pub on_no_changes: bool,
+    #[serde(default = "default_notify_on_failure")]
+    pub on_failure: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            on_success: false,
+            on_no_changes: false,
+            on_failure: default_notify_on_failure(),
+        }
+    }
+}
+
+impl NotificationsConfig {
+    pub fn is_default(&self) -> bool {
+        *self == NotificationsConfig::default()
+    }
+}
+
+/// How interactive prompts are shown.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct UiConfig {
+    #[serde(default)]
+    pub selector: SelectorKind,
+    /// Launcher for the `dmenu` selector (defaults to the first of walker, fuzzel, wofi, rofi).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dmenu_command: Option<String>,
+}
+
+impl UiConfig {
+    pub fn is_default(&self) -> bool {
+        *self == UiConfig::default()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct SyncConfig {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "RepoConfig::is_unset")]
     pub repo: RepoConfig,
     pub files: FileConfig,
+    #[serde(default, skip_serializing_if = "StorageConfig::is_default")]
+    pub storage: StorageConfig,
+    #[serde(default, skip_serializing_if = "PackagesConfig::is_default")]
+    pub packages: PackagesConfig,
+    #[serde(default, skip_serializing_if = "HooksConfig::is_default")]
+    pub hooks: HooksConfig,
+    #[serde(default, skip_serializing_if = "NotificationsConfig::is_default")]
+    pub notifications: NotificationsConfig,
+    #[serde(default, skip_serializing_if = "UiConfig::is_default")]
+    pub ui: UiConfig,
+}
+
+/// A `SyncConfig` serialized without skipping default sections.
+#[derive(Debug, Serialize)]
+pub struct EffectiveConfig<'a> {
+    pub version: u32,
+    pub repo: &'a RepoConfig,
+    pub files: &'a FileConfig,
+    pub storage: &'a StorageConfig,
+    pub packages: &'a PackagesConfig,
+    pub hooks: &'a HooksConfig,
+    pub notifications: &'a NotificationsConfig,
+    pub ui: &'a UiConfig,
 }
 
 impl SyncConfig {
@@ -46,6 +290,52 @@ impl SyncConfig {
         Ok(set.into_iter().collect())
     }
 
+    /// Configured bundles that contribute at least one of `paths` (or a parent of one).
+    pub fn bundles_covering(&self, paths: &[String]) -> Vec<String> {
+        self.sorted_bundles()
+            .into_iter()
+            .filter(|id| {
+                bundles::find(id).is_some_and(|bundle| {
+                    bundle.paths.iter().any(|path| {
+                        paths
+                            .iter()
+                            .any(|selected| Path::new(selected).starts_with(path))
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// The subset of `paths` deployed as symlinks into the local clone.
+    pub fn linked_paths(&self, paths: &[String]) -> Result<Vec<String>> {
+        let linked: Vec<String> = paths
+            .iter()
+            .filter(|path| self.files.mode_for(path) == DeployMode::Link)
+            .cloned()
+            .collect();
+        if !linked.is_empty() && self.storage.kind != StorageKind::Git {
+            anyhow::bail!(
+                "mode = \"link\" needs git storage, which keeps a local clone to link to; storage kind '{}' does not.",
+                self.storage.kind.as_str()
+            );
+        }
+        Ok(linked)
+    }
+
+    /// Every section, including the ones left at their defaults.
+    pub fn effective(&self) -> EffectiveConfig<'_> {
+        EffectiveConfig {
+            version: self.version,
+            repo: &self.repo,
+            files: &self.files,
+            storage: &self.storage,
+            packages: &self.packages,
+            hooks: &self.hooks,
+            notifications: &self.notifications,
+            ui: &self.ui,
+        }
+    }
+
     pub fn sorted_bundles(&self) -> Vec<String> {
         let mut bundles = self.files.bundles.clone();
         bundles.sort();
@@ -58,6 +348,26 @@ pub fn default_branch() -> String {
     "main".to_string()
 }
 
+pub fn default_keep() -> usize {
+    10
+}
+
+pub fn default_capture_packages() -> bool {
+    true
+}
+
+pub fn default_bundle_actions() -> bool {
+    true
+}
+
+pub fn default_hook_timeout() -> u64 {
+    30
+}
+
+pub fn default_notify_on_failure() -> bool {
+    true
+}
+
 pub fn config_dir() -> Result<PathBuf> {
     let base = if let Some(xdg) = std::env::var_os("XDG_CONFIG_HOME") {
         PathBuf::from(xdg)
@@ -68,11 +378,128 @@ pub fn config_dir() -> Result<PathBuf> {
     Ok(base.join(CONFIG_DIR_NAME))
 }
 
+pub fn data_dir() -> Result<PathBuf> {
+    let base = if let Some(xdg) = std::env::var_os("XDG_DATA_HOME") {
+        PathBuf::from(xdg)
+    } else {
+        let home = std::env::var_os("HOME").context("HOME environment variable not set")?;
+        PathBuf::from(home).join(".local/share")
+    };
+    Ok(base.join(CONFIG_DIR_NAME))
+}
+
+/// Read config from `path` instead of the default location (the global `--config` flag).
+pub fn set_config_path(path: PathBuf) {
+    let _ = CONFIG_PATH.set(path);
+}
+
+/// Use this selector backend regardless of config (the global `--selector` flag).
+pub fn set_selector(kind: SelectorKind) {
+    let _ = SELECTOR.set(kind);
+}
+
+/// The selector given with `--selector`, if any.
+pub fn selector_flag() -> Option<SelectorKind> {
+    SELECTOR.get().copied()
+}
+
+/// The config file in use: `--config`, then `$OMARCHY_SYNCD_CONFIG`, then the default.
 pub fn config_file_path() -> Result<PathBuf> {
-    Ok(config_dir()?.join("config.toml"))
+    if let Some(path) = CONFIG_PATH.get() {
+        return Ok(path.clone());
+    }
+    if let Some(path) = env_override(CONFIG_ENV) {
+        return Ok(PathBuf::from(shellexpand::tilde(&path).into_owned()));
+    }
+    default_config_file_path()
 }
 
-pub fn load_config() -> Result<SyncConfig> {
+pub fn default_config_file_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join(CONFIG_FILE_NAME))
+}
+
+/// A setting taken from the command line or environment instead of the config file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Override {
+    pub key: &'static str,
+    pub value: String,
+    pub source: String,
+}
+
+/// Every override currently in effect.
+pub fn overrides() -> Vec<Override> {
+    let mut overrides = Vec::new();
+    if let Some(path) = CONFIG_PATH.get() {
+        overrides.push(Override {
+            key: "config",
+            value: path.display().to_string(),
+            source: "--config".to_string(),
+        });
+    } else if let Some(path) = env_override(CONFIG_ENV) {
+        overrides.push(Override {
+            key: "config",
+            value: path,
+            source: CONFIG_ENV.to_string(),
+        });
+    }
+    for (key, var) in [("repo.url", REPO_URL_ENV), ("repo.branch", BRANCH_ENV)] {
+        if let Some(value) = env_override(var) {
+            overrides.push(Override {
+                key,
+                value,
+                source: var.to_string(),
+            });
+        }
+    }
+    if let Some(kind) = SELECTOR.get() {
+        overrides.push(Override {
+            key: "ui.selector",
+            value: kind.as_str().to_string(),
+            source: "--selector".to_string(),
+        });
+    } else if let Some(value) = env_override(SELECTOR_ENV) {
+        overrides.push(Override {
+            key: "ui.selector",
+            value,
+            source: SELECTOR_ENV.to_string(),
+        });
+    }
+    overrides
+}
+
+/// Replace repo settings with `$OMARCHY_SYNCD_REPO_URL` / `$OMARCHY_SYNCD_BRANCH` when set,
+/// and the selector with `--selector` / `$OMARCHY_SYNCD_SELECTOR`.
+pub fn apply_overrides(cfg: &mut SyncConfig) {
+    if let Some(url) = env_override(REPO_URL_ENV) {
+        cfg.repo.url = url;
+    }
+    if let Some(branch) = env_override(BRANCH_ENV) {
+        cfg.repo.branch = branch;
+    }
+    apply_ui_overrides(&mut cfg.ui);
+}
+
+/// Apply the selector overrides alone, for commands that run before a config exists.
+pub fn apply_ui_overrides(ui: &mut UiConfig) {
+    if let Some(kind) = SELECTOR.get() {
+        ui.selector = *kind;
+    } else if let Some(value) = env_override(SELECTOR_ENV) {
+        match SelectorKind::from_str(&value, true) {
+            Ok(kind) => ui.selector = kind,
+            Err(_) => eprintln!("Ignoring ${SELECTOR_ENV}: unknown selector '{value}'."),
+        }
+    }
+}
+
+fn env_override(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Read the config file without interpreting it.
+pub fn read_config_source() -> Result<(PathBuf, String)> {
     let path = config_file_path()?;
     let raw = fs::read_to_string(&path).with_context(|| {
         format!(
@@ -80,22 +507,176 @@ pub fn load_config() -> Result<SyncConfig> {
             path.display()
         )
     })?;
-    let cfg: SyncConfig = toml::from_str(&raw)
-        .with_context(|| format!("Failed to parse config TOML at {}", path.display()))?;
+    Ok((path, raw))
+}
+
+/// Load the config, failing with every validation error at once.
+///
+/// Older schema versions are upgraded in memory only, so read-only commands never rewrite
+/// the file; `config migrate` (or any command that edits the config) writes the upgrade.
+pub fn load_config() -> Result<SyncConfig> {
+    let (path, raw) = read_config_source()?;
+    let migration = migrate_source(&raw)?;
+    let current = migration
+        .as_ref()
+        .map_or(raw.as_str(), |m| m.after.as_str());
+    let errors = config_errors(current);
+    if !errors.is_empty() {
+        // Locate problems in the file as written when the upgrade left them unchanged.
+        let original = config_errors(&raw);
+        let same = |a: &[Problem], b: &[Problem]| {
+            a.iter()
+                .map(|problem| &problem.message)
+                .eq(b.iter().map(|problem| &problem.message))
+        };
+        let (location, errors) = match &migration {
+            Some(_) if same(&original, &errors) => (path.display().to_string(), original),
+            Some(m) => (
+                format!(
+                    "{} (locations refer to the file upgraded from version {}; run 'omarchy-syncd config migrate' first)",
+                    path.display(),
+                    m.from
+                ),
+                errors,
+            ),
+            None => (path.display().to_string(), errors),
+        };
+        let lines: Vec<String> = errors
+            .iter()
+            .map(|problem| format!("  {problem}"))
+            .collect();
+        anyhow::bail!("Invalid config at {location}:\n{}", lines.join("\n"));
+    }
+
+    let mut cfg = parse_config(&path, current)?;
+    apply_overrides(&mut cfg);
     Ok(cfg)
 }
 
+fn config_errors(raw: &str) -> Vec<Problem> {
+    validate::check(raw)
+        .into_iter()
+        .filter(|problem| problem.severity == Severity::Error)
+        .collect()
+}
+
+/// Deserialize config contents without the checks `load_config` runs first.
+pub fn parse_config(path: &Path, raw: &str) -> Result<SyncConfig> {
+    toml::from_str(raw)
+        .with_context(|| format!("Failed to parse config TOML at {}", path.display()))
+}
+
+/// Parse config contents kept somewhere other than the config file (e.g. in a snapshot),
+/// upgrading them to the current schema first. `origin` only labels errors.
+pub fn config_from_source(origin: &Path, raw: &str) -> Result<SyncConfig> {
+    let migration = migrate_source(raw)?;
+    parse_config(origin, migration.as_ref().map_or(raw, |m| m.after.as_str()))
+}
+
+/// Where a snapshot keeps its copy of config.toml, next to `symlinks.json`.
+pub fn snapshot_config_path(repo_dir: &Path) -> PathBuf {
+    fs_ops::repo_metadata_dir(repo_dir).join(CONFIG_FILE_NAME)
+}
+
+/// Copy the default config file into a snapshot so a new machine can `bootstrap` from it.
+///
+/// A file passed with `--config` or `$OMARCHY_SYNCD_CONFIG` is never stored, and the copy is
+/// taken as written, so URL and branch overrides from the environment stay out of it too.
+/// Returns `false` (leaving any earlier copy alone) when there is no default config file.
+pub fn snapshot_config(repo_dir: &Path) -> Result<bool> {
+    let path = default_config_file_path()?;
+    if !path.exists() {
+        return Ok(false);
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let dest = snapshot_config_path(repo_dir);
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(&dest, raw).with_context(|| format!("Failed to write {}", dest.display()))?;
+    Ok(true)
+}
+
+/// A config file upgraded to `CONFIG_VERSION`.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub from: u32,
+    pub to: u32,
+    pub before: String,
+    pub after: String,
+}
+
+/// Schema version declared by a config file, or `None` if it cannot be read (validation reports why).
+pub fn source_version(raw: &str) -> Option<u32> {
+    let doc = raw.parse::<DocumentMut>().ok()?;
+    match doc.get("version") {
+        None => Some(0),
+        Some(item) => item.as_integer().and_then(|v| u32::try_from(v).ok()),
+    }
+}
+
+/// Upgrade config contents to `CONFIG_VERSION`, or `None` when they are already current.
+pub fn migrate_source(raw: &str) -> Result<Option<Migration>> {
+    let Some(from) = source_version(raw) else {
+        return Ok(None);
+    };
+    if from > CONFIG_VERSION {
+        anyhow::bail!(
+            "Config version {from} is newer than this omarchy-syncd supports (version {CONFIG_VERSION}). Upgrade omarchy-syncd to use this config."
+        );
+    }
+    if from == CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    let mut doc = raw.parse::<DocumentMut>()?;
+    for (step, migrate) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        migrate(&mut doc).with_context(|| {
+            format!(
+                "Failed migrating config from version {step} to {}",
+                step + 1
+            )
+        })?;
+        doc.insert("version", toml_edit::value(i64::from(step as u32 + 1)));
+    }
+    Ok(Some(Migration {
+        from,
+        to: CONFIG_VERSION,
+        before: raw.to_string(),
+        after: doc.to_string(),
+    }))
+}
+
+/// Where the pre-migration file is kept.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Copy the current file to its `.bak` path, then write the migrated contents.
+pub fn write_migration(path: &Path, migration: &Migration) -> Result<()> {
+    let backup = backup_path(path);
+    fs::write(&backup, &migration.before)
+        .with_context(|| format!("Failed to write config backup {}", backup.display()))?;
+    fs::write(path, &migration.after)
+        .with_context(|| format!("Failed to write config file {}", path.display()))
+}
+
 pub fn write_config(cfg: &SyncConfig) -> Result<()> {
-    let dir = config_dir()?;
-    fs::create_dir_all(&dir)
-        .with_context(|| format!("Failed to create config directory {}", dir.display()))?;
+    let path = config_file_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create config directory {}", dir.display()))?;
+    }
     let mut normalized = cfg.clone();
+    normalized.version = CONFIG_VERSION;
     normalized.files.paths.sort();
     normalized.files.paths.dedup();
     normalized.files.bundles = normalized.sorted_bundles();
     bundles::ensure_known(&normalized.files.bundles)?;
     let raw = toml::to_string_pretty(&normalized)?;
-    let path = dir.join("config.toml");
     fs::write(&path, raw)
         .with_context(|| format!("Failed to write config file {}", path.display()))?;
     Ok(())
@@ -0,0 +1,259 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result, bail};
+use toml_edit::{Array, DocumentMut, Item, Table, Value};
+
+use crate::config::{
+    self, HooksConfig, Migration, NotificationsConfig, PackagesConfig, RepoConfig, StorageConfig,
+    UiConfig,
+};
+use crate::fs_ops::DeployMode;
+use crate::validate::{self, Severity};
+
+/// `config.toml` opened for edits that keep its comments and formatting intact.
+pub struct ConfigDocument {
+    path: PathBuf,
+    doc: DocumentMut,
+    migration: Option<Migration>,
+}
+
+impl ConfigDocument {
+    /// Open the config file, upgrading it in memory if it uses an older schema version.
+    pub fn open() -> Result<Self> {
+        let (path, raw) = config::read_config_source()?;
+        Self::from_source(path, &raw)
+    }
+
+    /// Edit `raw` as if it were read from `path`; `save` writes it there.
+    pub fn from_source(path: PathBuf, raw: &str) -> Result<Self> {
+        let migration = config::migrate_source(raw)?;
+        let current = migration.as_ref().map_or(raw, |m| m.after.as_str());
+        let doc = current
+            .parse::<DocumentMut>()
+            .with_context(|| format!("Failed to parse config TOML at {}", path.display()))?;
+        Ok(Self {
+            path,
+            doc,
+            migration,
+        })
+    }
+
+    /// The file as it will be written by `save`.
+    pub fn contents(&self) -> String {
+        self.doc.to_string()
+    }
+
+    /// Value of `section.key`, falling back to the default when the file does not set it.
+    pub fn get(&self, key: &str) -> Result<toml::Value> {
+        let (section, field) = split_key(key)?;
+        let explicit = match section {
+            Some(section) => self
+                .doc
+                .get(section)
+                .and_then(Item::as_table_like)
+                .and_then(|table| table.get(field)),
+            None => self.doc.get(field),
+        };
+        if let Some(item) = explicit {
+            // Reparse through `toml` so callers get plain values instead of decorated ones.
+            let raw = format!("value = {}", item.to_string().trim());
+            let mut table: toml::Table = toml::from_str(&raw)
+                .with_context(|| format!("`{key}` does not hold a single value"))?;
+            return table
+                .remove("value")
+                .with_context(|| format!("`{key}` does not hold a single value"));
+        }
+        default_value(section, field)?.with_context(|| format!("`{key}` is not set"))
+    }
+
+    /// Set `section.key`. `raw` is read as a TOML value when it parses as one (`true`, `10`,
+    /// `["a", "b"]`, `"quoted"`) and as a plain string otherwise. Returns whether anything changed.
+    pub fn set(&mut self, key: &str, raw: &str) -> Result<bool> {
+        let (section, field) = split_key(key)?;
+        let value = raw
+            .parse::<Value>()
+            .unwrap_or_else(|_| Value::from(raw.to_string()));
+        let target = match section {
+            Some(section) => self.section_mut(section)?,
+            None => self.doc.as_table_mut(),
+        };
+        if let Some(existing) = target.get(field).and_then(Item::as_value)
+            && undecorated(existing) == undecorated(&value)
+        {
+            return Ok(false);
+        }
+        match target.get_mut(field).and_then(Item::as_value_mut) {
+            Some(existing) => {
+                // Keep the spacing and trailing comment around the old value.
+                let decor = existing.decor().clone();
+                *existing = value;
+                *existing.decor_mut() = decor;
+            }
+            None => {
+                target.insert(field, toml_edit::value(value));
+            }
+        }
+        Ok(true)
+    }
+
+    /// Append `entry` to the `[files]` list `field` unless it is already there.
+    pub fn add_to_list(&mut self, field: &str, entry: &str) -> Result<bool> {
+        let list = self.files_list(field)?;
+        if list.iter().any(|value| value.as_str() == Some(entry)) {
+            return Ok(false);
+        }
+        // One entry per line stays one entry per line; inline lists keep the default spacing.
+        let indent = list
+            .iter()
+            .last()
+            .and_then(|value| value.decor().prefix())
+            .and_then(|prefix| prefix.as_str())
+            .and_then(|prefix| prefix.rsplit_once('\n'))
+            .map(|(_, indent)| indent.to_string());
+        // A comment after the last entry lives in the list's trailing text; keep it on that
+        // entry's line instead of letting it drift below the new one.
+        let prefix = indent.map(|indent| {
+            let trailing = list.trailing().as_str().unwrap_or_default().trim_end();
+            let prefix = format!("{trailing}\n{indent}");
+            list.set_trailing("\n");
+            prefix
+        });
+        list.push(entry);
+        if let (Some(prefix), Some(pushed)) = (prefix, list.iter_mut().last()) {
+            pushed.decor_mut().set_prefix(prefix);
+        }
+        Ok(true)
+    }
+
+    /// Remove `entry` from the `[files]` list `field`, returning whether it was present.
+    pub fn remove_from_list(&mut self, field: &str, entry: &str) -> Result<bool> {
+        let list = self.files_list(field)?;
+        let before = list.len();
+        list.retain(|value| value.as_str() != Some(entry));
+        // An inline list whose first entry went away would otherwise start with `[ "…"`.
+        if let Some(first) = list.get_mut(0)
+            && first
+                .decor()
+                .prefix()
+                .and_then(|prefix| prefix.as_str())
+                .is_some_and(|prefix| !prefix.contains('\n'))
+        {
+            first.decor_mut().set_prefix("");
+        }
+        Ok(list.len() != before)
+    }
+
+    /// Make the `[files]` list `field` hold exactly `entries`: entries no longer wanted are
+    /// removed and new ones appended, so the ones kept stay where they are with their comments.
+    pub fn replace_list(&mut self, field: &str, entries: &[String]) -> Result<bool> {
+        let stale: Vec<String> = self
+            .files_list(field)?
+            .iter()
+            .filter_map(|value| value.as_str())
+            .filter(|value| !entries.iter().any(|entry| entry == value))
+            .map(str::to_string)
+            .collect();
+        let mut changed = false;
+        for entry in &stale {
+            changed |= self.remove_from_list(field, entry)?;
+        }
+        for entry in entries {
+            changed |= self.add_to_list(field, entry)?;
+        }
+        Ok(changed)
+    }
+
+    /// Validate the edited file and write it, keeping a `.bak` copy of a file it upgraded.
+    pub fn save(&self) -> Result<()> {
+        let raw = self.doc.to_string();
+        let errors: Vec<String> = validate::check(&raw)
+            .into_iter()
+            .filter(|problem| problem.severity == Severity::Error)
+            .map(|problem| format!("  {problem}"))
+            .collect();
+        if !errors.is_empty() {
+            bail!(
+                "Refusing to write {}; the change would leave it invalid:\n{}",
+                self.path.display(),
+                errors.join("\n")
+            );
+        }
+        if let Some(migration) = &self.migration
+            && self.path.exists()
+        {
+            let backup = config::backup_path(&self.path);
+            fs::write(&backup, &migration.before)
+                .with_context(|| format!("Failed to write config backup {}", backup.display()))?;
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create config directory {}", dir.display()))?;
+        }
+        fs::write(&self.path, raw)
+            .with_context(|| format!("Failed to write config file {}", self.path.display()))
+    }
+
+    fn section_mut(&mut self, section: &str) -> Result<&mut Table> {
+        let item = self
+            .doc
+            .entry(section)
+            .or_insert_with(|| Item::Table(Table::new()));
+        item.as_table_mut()
+            .with_context(|| format!("[{section}] is not a table"))
+    }
+
+    fn files_list(&mut self, field: &str) -> Result<&mut Array> {
+        let files = self.section_mut("files")?;
+        files
+            .entry(field)
+            .or_insert_with(|| toml_edit::value(Array::new()))
+            .as_array_mut()
+            .with_context(|| format!("files.{field} is not a list"))
+    }
+}
+
+fn undecorated(value: &Value) -> String {
+    let mut value = value.clone();
+    value.decor_mut().clear();
+    value.to_string()
+}
+
+/// Split `section.key` (or the top-level `version`) and make sure it names a known setting.
+fn split_key(key: &str) -> Result<(Option<&str>, &str)> {
+    if key == "version" {
+        return Ok((None, key));
+    }
+    let Some((section, field)) = key.split_once('.') else {
+        bail!("Expected a key like `repo.branch`, got `{key}`");
+    };
+    let Some(fields) = validate::section_keys(section) else {
+        bail!("Unknown config section `{section}`");
+    };
+    if !fields.contains(&field) {
+        bail!(
+            "Unknown key `{field}` in [{section}]; expected one of: {}",
+            fields.join(", ")
+        );
+    }
+    Ok((Some(section), field))
+}
+
+fn default_value(section: Option<&str>, field: &str) -> Result<Option<toml::Value>> {
+    let defaults = match section {
+        None => return Ok(Some(toml::Value::Integer(config::CONFIG_VERSION.into()))),
+        Some("repo") => toml::Value::try_from(RepoConfig::default())?,
+        Some("storage") => toml::Value::try_from(StorageConfig::default())?,
+        Some("packages") => toml::Value::try_from(PackagesConfig::default())?,
+        Some("hooks") => toml::Value::try_from(HooksConfig::default())?,
+        Some("notifications") => toml::Value::try_from(NotificationsConfig::default())?,
+        Some("ui") => toml::Value::try_from(UiConfig::default())?,
+        Some(_) => {
+            return Ok(Some(match field {
+                "mode" => toml::Value::try_from(DeployMode::default())?,
+                "modes" => toml::Value::Table(toml::Table::new()),
+                _ => toml::Value::Array(Vec::new()),
+            }));
+        }
+    };
+    Ok(defaults.get(field).cloned())
+}
@@ -0,0 +1,329 @@
+use std::io::IsTerminal;
+
+use serde::Serialize;
+
+use crate::bundles;
+use crate::config::{self, SyncConfig};
+use crate::fs_ops;
+use crate::git;
+use crate::packages::CommandRunner;
+use crate::storage::StorageKind;
+use crate::validate;
+
+/// Oldest git release that supports everything we run (`git init -b`).
+const MIN_GIT_VERSION: (u32, u32) = (2, 28);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        }
+    }
+}
+
+/// One line of the doctor checklist.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Run every check, skipping the ones that need a config when it cannot be loaded.
+pub fn run(runner: &dyn CommandRunner) -> Vec<Check> {
+    let mut checks = vec![check_git(runner)];
+
+    let (check, cfg) = check_config();
+    checks.push(check);
+    if let Some(check) = check_overrides() {
+        checks.push(check);
+    }
+
+    if let Some(cfg) = &cfg {
+        checks.push(check_storage(cfg));
+        if let Some(check) = check_ssh(runner, cfg) {
+            checks.push(check);
+        }
+        checks.push(check_bundles(cfg));
+        checks.push(check_paths(cfg));
+    }
+
+    checks.push(check_symlinks());
+    checks.push(check_program(
+        runner,
+        "hyprctl",
+        "Hyprland reloads after restore will be skipped",
+    ));
+    checks.push(check_terminal());
+    checks
+}
+
+/// Validation problems fail the check, but the config is still used for the remaining
+/// checks whenever it deserializes.
+fn check_config() -> (Check, Option<SyncConfig>) {
+    let (path, raw) = match config::read_config_source() {
+        Ok(source) => source,
+        Err(err) => return (Check::new("config", Status::Fail, format!("{err:#}")), None),
+    };
+    let problems = validate::check(&raw);
+    let status = if validate::has_errors(&problems) {
+        Status::Fail
+    } else if problems.is_empty() {
+        Status::Pass
+    } else {
+        Status::Warn
+    };
+    let detail = if problems.is_empty() {
+        format!("{} is valid", path.display())
+    } else {
+        problems
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+    let cfg = config::parse_config(&path, &raw).ok().map(|mut cfg| {
+        config::apply_overrides(&mut cfg);
+        cfg
+    });
+    (Check::new("config", status, detail), cfg)
+}
+
+/// Lists settings taken from `--config` or the environment; `None` when there are none.
+fn check_overrides() -> Option<Check> {
+    let overrides = config::overrides();
+    if overrides.is_empty() {
+        return None;
+    }
+    let detail = overrides
+        .iter()
+        .map(|item| format!("{} = {} (from {})", item.key, item.value, item.source))
+        .collect::<Vec<_>>()
+        .join("; ");
+    Some(Check::new("overrides", Status::Pass, detail))
+}
+
+fn check_git(runner: &dyn CommandRunner) -> Check {
+    if !runner.available("git") {
+        return Check::new("git", Status::Fail, "git was not found on PATH");
+    }
+    let version = match runner.output("git", &["--version"]) {
+        Ok(out) => out.trim().to_string(),
+        Err(err) => return Check::new("git", Status::Fail, format!("{err:#}")),
+    };
+    match parse_git_version(&version) {
+        Some(found) if found < MIN_GIT_VERSION => Check::new(
+            "git",
+            Status::Warn,
+            format!(
+                "{version}; {}.{} or newer is recommended",
+                MIN_GIT_VERSION.0, MIN_GIT_VERSION.1
+            ),
+        ),
+        _ => Check::new("git", Status::Pass, version),
+    }
+}
+
+fn parse_git_version(raw: &str) -> Option<(u32, u32)> {
+    let numbers = raw.split_whitespace().nth(2)?;
+    let mut parts = numbers.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+fn check_storage(cfg: &SyncConfig) -> Check {
+    match cfg.storage.kind {
+        StorageKind::Git => {
+            if cfg.repo.url.trim().is_empty() {
+                return Check::new("remote", Status::Fail, "no `url` set under [repo]");
+            }
+            match git::verify_remote(&cfg.repo.url, &cfg.repo.branch) {
+                Ok(()) => Check::new(
+                    "remote",
+                    Status::Pass,
+                    format!("branch '{}' found on {}", cfg.repo.branch, cfg.repo.url),
+                ),
+                Err(err) => Check::new("remote", Status::Fail, format!("{err:#}")),
+            }
+        }
+        kind => {
+            let Some(raw) = cfg.storage.path.as_deref() else {
+                return Check::new(
+                    "storage",
+                    Status::Fail,
+                    format!(
+                        "storage kind '{}' needs `path` under [storage]",
+                        kind.as_str()
+                    ),
+                );
+            };
+            let path = shellexpand::tilde(raw).into_owned();
+            if std::path::Path::new(&path).is_dir() {
+                Check::new("storage", Status::Pass, format!("{path} is available"))
+            } else {
+                Check::new(
+                    "storage",
+                    Status::Fail,
+                    format!("{path} does not exist or is not mounted"),
+                )
+            }
+        }
+    }
+}
+
+/// Only relevant for SSH remotes; returns `None` otherwise.
+fn check_ssh(runner: &dyn CommandRunner, cfg: &SyncConfig) -> Option<Check> {
+    let url = cfg.repo.url.trim();
+    let is_ssh = cfg.storage.kind == StorageKind::Git
+        && (url.starts_with("ssh://") || (url.contains('@') && url.contains(':')));
+    if !is_ssh {
+        return None;
+    }
+    if std::env::var_os("SSH_AUTH_SOCK").is_none() || !runner.available("ssh-add") {
+        return Some(Check::new(
+            "ssh",
+            Status::Warn,
+            "no SSH agent found; pushes rely on an unencrypted key or will prompt",
+        ));
+    }
+    Some(match runner.output("ssh-add", &["-l"]) {
+        Ok(keys) => Check::new(
+            "ssh",
+            Status::Pass,
+            format!("{} key(s) loaded in the SSH agent", keys.lines().count()),
+        ),
+        Err(_) => Check::new(
+            "ssh",
+            Status::Warn,
+            "the SSH agent has no keys loaded; run `ssh-add`",
+        ),
+    })
+}
+
+fn check_bundles(cfg: &SyncConfig) -> Check {
+    let unknown: Vec<&str> = cfg
+        .files
+        .bundles
+        .iter()
+        .filter(|id| bundles::find(id).is_none())
+        .map(String::as_str)
+        .collect();
+    if unknown.is_empty() {
+        Check::new(
+            "bundles",
+            Status::Pass,
+            format!("{} bundle(s) configured", cfg.files.bundles.len()),
+        )
+    } else {
+        Check::new(
+            "bundles",
+            Status::Fail,
+            format!("unknown bundle(s): {}", unknown.join(", ")),
+        )
+    }
+}
+
+fn check_paths(cfg: &SyncConfig) -> Check {
+    let mut paths: Vec<String> = cfg.files.paths.clone();
+    for id in &cfg.files.bundles {
+        if let Some(bundle) = bundles::find(id) {
+            paths.extend(bundle.paths.iter().map(|path| path.to_string()));
+        }
+    }
+    paths.sort();
+    paths.dedup();
+
+    let mut outside = Vec::new();
+    let mut missing = Vec::new();
+    for raw in &paths {
+        match fs_ops::resolve_tracked_path(raw) {
+            Ok(path) if std::fs::symlink_metadata(&path).is_err() => missing.push(raw.as_str()),
+            Ok(_) => {}
+            Err(_) => outside.push(raw.as_str()),
+        }
+    }
+
+    if !outside.is_empty() {
+        Check::new(
+            "paths",
+            Status::Fail,
+            format!("outside $HOME: {}", outside.join(", ")),
+        )
+    } else if !missing.is_empty() {
+        Check::new(
+            "paths",
+            Status::Warn,
+            format!("missing on this machine: {}", missing.join(", ")),
+        )
+    } else {
+        Check::new(
+            "paths",
+            Status::Pass,
+            format!("{} tracked path(s) present", paths.len()),
+        )
+    }
+}
+
+fn check_symlinks() -> Check {
+    match fs_ops::broken_symlinks() {
+        Ok(broken) if broken.is_empty() => {
+            Check::new("symlinks", Status::Pass, "recorded symlinks are intact")
+        }
+        Ok(broken) => Check::new("symlinks", Status::Warn, broken.join("; ")),
+        Err(err) => Check::new("symlinks", Status::Fail, format!("{err:#}")),
+    }
+}
+
+fn check_program(runner: &dyn CommandRunner, program: &'static str, impact: &str) -> Check {
+    if runner.available(program) {
+        Check::new(program, Status::Pass, "found")
+    } else {
+        Check::new(program, Status::Warn, format!("not found; {impact}"))
+    }
+}
+
+/// The skim selector needs an interactive terminal that understands cursor movement.
+fn check_terminal() -> Check {
+    let term = std::env::var("TERM").unwrap_or_default();
+    let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    if !interactive {
+        Check::new(
+            "terminal",
+            Status::Warn,
+            "not attached to a terminal; skim selectors are skipped (use --no-ui, --path or --selector dmenu)",
+        )
+    } else if term.is_empty() || term == "dumb" {
+        Check::new(
+            "terminal",
+            Status::Warn,
+            format!("TERM={term:?} does not support the skim selector; use --selector prompt"),
+        )
+    } else {
+        Check::new("terminal", Status::Pass, format!("TERM={term}"))
+    }
+}
+
+pub fn count(checks: &[Check], status: Status) -> usize {
+    checks.iter().filter(|check| check.status == status).count()
+}
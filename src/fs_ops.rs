@@ -1,6 +1,8 @@
 use std::{
-    fs,
+    fs::{self, File},
+    io::Read,
     path::{Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 
 use anyhow::{Context, Result};
@@ -9,12 +11,52 @@ use serde::{Deserialize, Serialize};
 use serde_json;
 use walkdir::WalkDir;
 
+use crate::output::Skipped;
+use crate::say;
+
 #[cfg(unix)]
 use std::os::unix::fs::symlink;
 
 const REPO_METADATA_DIR: &str = ".config/omarchy-syncd";
 const SYMLINK_METADATA_FILE: &str = "symlinks.json";
 
+/// Result of copying tracked paths into or out of a snapshot.
+#[derive(Debug, Default, Serialize)]
+pub struct Transfer {
+    /// Paths whose contents differed from the other side and were copied.
+    pub changed: Vec<String>,
+    /// Paths that were left alone, with the reason.
+    pub skipped: Vec<Skipped>,
+}
+
+impl Transfer {
+    fn skip(&mut self, path: &str, reason: impl Into<String>) {
+        let reason = reason.into();
+        say!("Skipping {path} because {reason}.");
+        self.skipped.push(Skipped {
+            path: path.to_string(),
+            reason,
+        });
+    }
+}
+
+/// How restore puts a tracked path into `$HOME`, selected via `files.mode`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeployMode {
+    /// Copy files out of the snapshot; local edits are picked up by the next backup.
+    #[default]
+    Copy,
+    /// Symlink every file to the persistent local clone, which becomes the source of truth.
+    Link,
+}
+
+impl DeployMode {
+    pub fn is_default(&self) -> bool {
+        *self == DeployMode::default()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct SymlinkEntry {
     path: String,
@@ -53,8 +95,13 @@ fn ensure_parent(path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Directory inside a snapshot that holds omarchy-syncd's own metadata files.
+pub fn repo_metadata_dir(repo_dir: &Path) -> PathBuf {
+    repo_dir.join(REPO_METADATA_DIR)
+}
+
 fn repo_metadata_path(repo_dir: &Path) -> PathBuf {
-    repo_dir.join(REPO_METADATA_DIR).join(SYMLINK_METADATA_FILE)
+    repo_metadata_dir(repo_dir).join(SYMLINK_METADATA_FILE)
 }
 
 fn user_metadata_path() -> Result<PathBuf> {
@@ -70,7 +117,7 @@ fn prune_git_dirs(path: &Path) -> Result<()> {
 
     for entry in WalkDir::new(path).into_iter().filter_map(Result::ok) {
         if entry.file_type().is_dir() && entry.file_name() == ".git" {
-            println!(
+            say!(
                 "Removing embedded .git directory at {}",
                 entry.path().display()
             );
@@ -137,7 +184,30 @@ fn collect_symlinks(
     Ok(())
 }
 
-pub fn snapshot(paths: &[String], repo_dir: &Path) -> Result<()> {
+fn recorded_symlinks_outside(paths: &[String], repo_dir: &Path) -> Result<Vec<SymlinkEntry>> {
+    let meta_path = repo_metadata_path(repo_dir);
+    if !meta_path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read_to_string(&meta_path)
+        .with_context(|| format!("Failed reading symlink metadata {}", meta_path.display()))?;
+    let entries: Vec<SymlinkEntry> =
+        serde_json::from_str(&data).with_context(|| "Failed parsing symlink metadata")?;
+    let home = home_dir()?;
+    let mut kept = Vec::new();
+    for entry in entries {
+        if tracked_path_containing(paths, &home.join(&entry.path))?.is_none() {
+            kept.push(entry);
+        }
+    }
+    Ok(kept)
+}
+
+/// Copy `paths` into the snapshot, reporting which changed and which were skipped.
+///
+/// Paths also listed in `links` are expected to be symlinked into `repo_dir` already; only
+/// files added or deleted locally since the last restore are brought over.
+pub fn snapshot(paths: &[String], links: &[String], repo_dir: &Path) -> Result<Transfer> {
     fs::create_dir_all(repo_dir).with_context(|| {
         format!(
             "Failed to create repository working directory {}",
@@ -145,15 +215,22 @@ pub fn snapshot(paths: &[String], repo_dir: &Path) -> Result<()> {
         )
     })?;
 
-    let mut symlink_entries: Vec<SymlinkEntry> = Vec::new();
+    // Keep what earlier snapshots recorded for paths outside this selection.
+    let mut symlink_entries = recorded_symlinks_outside(paths, repo_dir)?;
+    let mut transfer = Transfer::default();
 
     for raw in paths {
         let expanded = expand_path(raw)?;
+        if links.contains(raw) {
+            if fs::symlink_metadata(&expanded).is_err() {
+                transfer.skip(raw, "it does not exist on this machine");
+            } else if adopt_linked(&expanded, repo_dir, &mut symlink_entries)? {
+                transfer.changed.push(raw.clone());
+            }
+            continue;
+        }
         if !expanded.exists() {
-            println!(
-                "Skipping {} because it does not exist on this machine.",
-                raw
-            );
+            transfer.skip(raw, "it does not exist on this machine");
             continue;
         }
 
@@ -170,21 +247,19 @@ pub fn snapshot(paths: &[String], repo_dir: &Path) -> Result<()> {
 
         let rel = relative_to_home(&expanded)?;
         let dest = repo_dir.join(&rel);
+        let differs = !same_contents(&dest, &expanded)?;
 
         if expanded.is_dir() {
-            if dest.exists() {
-                if let Err(err) = fs::remove_dir_all(&dest) {
-                    println!(
-                        "Skipping {} because destination cleanup failed: {}",
-                        raw, err
-                    );
-                    continue;
-                }
+            if dest.exists()
+                && let Err(err) = fs::remove_dir_all(&dest)
+            {
+                transfer.skip(raw, format!("destination cleanup failed: {err}"));
+                continue;
             }
             if let Err(err) = ensure_parent(&dest) {
-                println!(
-                    "Skipping {} because destination parent could not be created: {}",
-                    raw, err
+                transfer.skip(
+                    raw,
+                    format!("destination parent could not be created: {err}"),
                 );
                 continue;
             }
@@ -192,41 +267,39 @@ pub fn snapshot(paths: &[String], repo_dir: &Path) -> Result<()> {
             options.copy_inside = false;
             options.overwrite = true;
             if let Err(err) = dir::copy(&expanded, dest.parent().unwrap_or(repo_dir), &options) {
-                println!("Skipping {} because copy failed: {}", raw, err);
+                transfer.skip(raw, format!("copy failed: {err}"));
                 continue;
             }
 
             if let Err(err) = prune_git_dirs(&dest) {
-                println!(
-                    "Skipping {} because embedded git cleanup failed: {}",
-                    raw, err
-                );
+                transfer.skip(raw, format!("embedded git cleanup failed: {err}"));
                 continue;
             }
 
             collect_symlinks(&expanded, repo_dir, &mut symlink_entries)?;
         } else {
-            if dest.exists() {
-                if let Err(err) = fs::remove_file(&dest) {
-                    println!(
-                        "Skipping {} because destination cleanup failed: {}",
-                        raw, err
-                    );
-                    continue;
-                }
+            if dest.exists()
+                && let Err(err) = fs::remove_file(&dest)
+            {
+                transfer.skip(raw, format!("destination cleanup failed: {err}"));
+                continue;
             }
             if let Err(err) = ensure_parent(&dest) {
-                println!(
-                    "Skipping {} because destination parent could not be created: {}",
-                    raw, err
+                transfer.skip(
+                    raw,
+                    format!("destination parent could not be created: {err}"),
                 );
                 continue;
             }
             if let Err(err) = fs::copy(&expanded, &dest) {
-                println!("Skipping {} because copy failed: {}", raw, err);
+                transfer.skip(raw, format!("copy failed: {err}"));
                 continue;
             }
         }
+
+        if differs {
+            transfer.changed.push(raw.clone());
+        }
     }
 
     let meta_path = repo_metadata_path(repo_dir);
@@ -245,22 +318,35 @@ pub fn snapshot(paths: &[String], repo_dir: &Path) -> Result<()> {
             .with_context(|| format!("Failed writing symlink metadata {}", meta_path.display()))?;
     }
 
-    Ok(())
+    Ok(transfer)
 }
 
-pub fn restore(paths: &[String], repo_dir: &Path) -> Result<()> {
+/// Copy `paths` back from the snapshot, reporting which changed and which were skipped.
+///
+/// Paths also listed in `links` get a symlink per file pointing into `repo_dir` instead, so
+/// `repo_dir` must outlive the restore (the persistent git clone).
+pub fn restore(paths: &[String], links: &[String], repo_dir: &Path) -> Result<Transfer> {
+    let mut transfer = Transfer::default();
     for raw in paths {
         let expanded = expand_path(raw)?;
         let rel = relative_to_home(&expanded)?;
         let source = repo_dir.join(&rel);
         if !source.exists() {
-            println!(
-                "Skipping {} because it is not present in the repository.",
-                raw
-            );
+            transfer.skip(raw, "it is not present in the repository");
+            continue;
+        }
+
+        if links.contains(raw) {
+            if link_tree(&source, &expanded, repo_dir)? {
+                transfer.changed.push(raw.clone());
+            }
             continue;
         }
 
+        if !same_contents(&source, &expanded)? {
+            transfer.changed.push(raw.clone());
+        }
+
         if source.is_dir() {
             if expanded.exists() {
                 fs::remove_dir_all(&expanded).with_context(|| {
@@ -300,6 +386,15 @@ pub fn restore(paths: &[String], repo_dir: &Path) -> Result<()> {
         let home = home_dir()?;
         for entry in entries {
             let dest = home.join(&entry.path);
+            // Links outside the selected paths are left as they are.
+            let Some(raw) = tracked_path_containing(paths, &dest)? else {
+                continue;
+            };
+            let unchanged =
+                fs::read_link(&dest).is_ok_and(|current| current == Path::new(&entry.target));
+            if !unchanged && !transfer.changed.contains(&raw) {
+                transfer.changed.push(raw);
+            }
             if dest.exists() {
                 if dest.is_dir() {
                     fs::remove_dir_all(&dest)
@@ -364,5 +459,445 @@ pub fn restore(paths: &[String], repo_dir: &Path) -> Result<()> {
             .with_context(|| format!("Failed writing symlink metadata {}", user_meta.display()))?;
     }
 
-    Ok(())
+    Ok(transfer)
+}
+
+/// Replace `dest` (a file or tree under `$HOME`) with symlinks to the files under `source`.
+///
+/// Directories stay real so links recorded in `symlinks.json` can be recreated inside them,
+/// and local files that are not in the snapshot are left for the next backup to pick up.
+/// Returns whether anything had to change.
+fn link_tree(source: &Path, dest: &Path, repo_dir: &Path) -> Result<bool> {
+    let mut changed = false;
+    if source.is_file() {
+        return link_file(source, dest);
+    }
+    if fs::symlink_metadata(dest).is_ok_and(|meta| !meta.is_dir()) {
+        remove_entry(dest)?;
+    }
+    fs::create_dir_all(dest).with_context(|| format!("Failed creating {}", dest.display()))?;
+
+    let walker = WalkDir::new(source)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let rel = entry
+            .path()
+            .strip_prefix(source)
+            .with_context(|| format!("Failed relativising {}", entry.path().display()))?;
+        changed |= link_file(entry.path(), &dest.join(rel))?;
+    }
+
+    // Links left behind by files that were deleted from the snapshot.
+    for entry in WalkDir::new(dest).min_depth(1).follow_links(false) {
+        let entry = entry?;
+        if entry.file_type().is_symlink()
+            && !entry.path().exists()
+            && fs::read_link(entry.path()).is_ok_and(|target| target.starts_with(repo_dir))
+        {
+            remove_entry(entry.path())?;
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+/// Point `dest` at `source`, replacing whatever is there; returns whether it changed.
+fn link_file(source: &Path, dest: &Path) -> Result<bool> {
+    if fs::read_link(dest).is_ok_and(|target| target == source) {
+        return Ok(false);
+    }
+    if fs::symlink_metadata(dest).is_ok() {
+        remove_entry(dest)?;
+    }
+    ensure_parent(dest)?;
+    #[cfg(unix)]
+    symlink(source, dest).with_context(|| {
+        format!(
+            "Failed creating symlink {} -> {}",
+            dest.display(),
+            source.display()
+        )
+    })?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(source, dest).with_context(|| {
+        format!(
+            "Failed creating symlink {} -> {}",
+            dest.display(),
+            source.display()
+        )
+    })?;
+    Ok(true)
+}
+
+/// Bring a linked path's local changes into the clone: files created locally are moved into
+/// it and replaced by links, files deleted locally are removed from it, and other symlinks
+/// are recorded like in copy mode. Edits made through the links are already in place.
+fn adopt_linked(expanded: &Path, repo_dir: &Path, entries: &mut Vec<SymlinkEntry>) -> Result<bool> {
+    let rel = relative_to_home(expanded)?;
+    let clone_root = repo_dir.join(&rel);
+    let mut changed = false;
+
+    // A tracked file that is already linked must not be copied onto itself.
+    for entry in WalkDir::new(expanded)
+        .follow_links(false)
+        .follow_root_links(false)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git")
+    {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let path_rel = relative_to_home(path)?;
+        let in_clone = repo_dir.join(&path_rel);
+        if entry.file_type().is_symlink() {
+            let target = fs::read_link(path)
+                .with_context(|| format!("Failed reading symlink target {}", path.display()))?;
+            if target != in_clone {
+                let is_dir = path.metadata().map(|m| m.is_dir()).unwrap_or(false);
+                register_symlink(repo_dir, path_rel, &target, is_dir, entries)?;
+            }
+            continue;
+        }
+        if in_clone.is_dir() {
+            fs::remove_dir_all(&in_clone)
+                .with_context(|| format!("Failed removing {}", in_clone.display()))?;
+        }
+        ensure_parent(&in_clone)?;
+        fs::copy(path, &in_clone)
+            .with_context(|| format!("Failed copying {} into the clone", path.display()))?;
+        link_file(&in_clone, path)?;
+        changed = true;
+    }
+
+    if clone_root.is_dir() {
+        let walker = WalkDir::new(&clone_root)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != ".git");
+        for entry in walker {
+            let entry = entry?;
+            if entry.file_type().is_dir() {
+                continue;
+            }
+            let home_path = expanded.join(entry.path().strip_prefix(&clone_root)?);
+            if fs::symlink_metadata(&home_path).is_err() {
+                fs::remove_file(entry.path())
+                    .with_context(|| format!("Failed removing {}", entry.path().display()))?;
+                changed = true;
+            }
+        }
+    }
+    Ok(changed)
+}
+
+/// Replace symlinks under `paths` that point into `repo_dir` with copies of their targets, so
+/// paths that left link mode keep their content once the clone is reset or removed. Links
+/// whose target is gone are dropped. Returns the paths that held any such link.
+pub fn unlink_tree(paths: &[String], repo_dir: &Path) -> Result<Vec<String>> {
+    let mut unlinked = Vec::new();
+    for raw in paths {
+        let expanded = expand_path(raw)?;
+        if fs::symlink_metadata(&expanded).is_err() {
+            continue;
+        }
+        let mut found = false;
+        for entry in WalkDir::new(&expanded)
+            .follow_links(false)
+            .follow_root_links(false)
+        {
+            let entry = entry?;
+            if !entry.file_type().is_symlink() {
+                continue;
+            }
+            let path = entry.path();
+            let target = fs::read_link(path)
+                .with_context(|| format!("Failed reading symlink target {}", path.display()))?;
+            if !target.starts_with(repo_dir) {
+                continue;
+            }
+            remove_entry(path)?;
+            if target.is_file() {
+                fs::copy(&target, path).with_context(|| {
+                    format!("Failed copying {} to {}", target.display(), path.display())
+                })?;
+            }
+            found = true;
+        }
+        if found {
+            unlinked.push(raw.clone());
+        }
+    }
+    Ok(unlinked)
+}
+
+fn remove_entry(path: &Path) -> Result<()> {
+    let is_dir = fs::symlink_metadata(path).is_ok_and(|meta| meta.is_dir());
+    if is_dir {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+    .with_context(|| format!("Failed removing {}", path.display()))
+}
+
+/// Whether `dest` holds the same file or tree as `source`.
+fn same_contents(source: &Path, dest: &Path) -> Result<bool> {
+    let Ok(metadata) = fs::symlink_metadata(dest) else {
+        return Ok(false);
+    };
+    if !source.exists() {
+        return Ok(false);
+    }
+    if source.is_dir() {
+        return Ok(metadata.is_dir() && trees_equal(source, dest, &[])?);
+    }
+    Ok(metadata.is_file() && files_equal(source, dest)?)
+}
+
+fn tracked_path_containing(paths: &[String], dest: &Path) -> Result<Option<String>> {
+    for raw in paths {
+        if dest.starts_with(expand_path(raw)?) {
+            return Ok(Some(raw.clone()));
+        }
+    }
+    Ok(None)
+}
+
+/// Paths a snapshot holds, for restoring it without a config: each app directory under
+/// `.config` and every other top-level entry, skipping git and omarchy-syncd metadata.
+pub fn snapshot_paths(repo_dir: &Path) -> Result<Vec<String>> {
+    let metadata = repo_metadata_dir(repo_dir);
+    let mut paths = Vec::new();
+    for dir in [repo_dir.to_path_buf(), repo_dir.join(".config")] {
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in
+            fs::read_dir(&dir).with_context(|| format!("Failed reading {}", dir.display()))?
+        {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default();
+            if name == ".git" || path == metadata || path == repo_dir.join(".config") {
+                continue;
+            }
+            let rel = path.strip_prefix(repo_dir)?;
+            paths.push(format!("~/{}", rel.display()));
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Files inside the tracked directory `raw` as they exist under `root` (`$HOME` or a
+/// snapshot), written as sub-paths of `raw`. Empty when `raw` is not a directory there.
+pub fn files_under(raw: &str, root: &Path) -> Result<Vec<String>> {
+    let dir = root.join(relative_to_home(&expand_path(raw)?)?);
+    if !dir.is_dir() || dir.is_symlink() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    let walker = WalkDir::new(&dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let rel = entry
+            .path()
+            .strip_prefix(&dir)
+            .with_context(|| format!("Failed relativising {}", entry.path().display()))?;
+        files.push(format!("{}/{}", raw.trim_end_matches('/'), rel.display()));
+    }
+    Ok(files)
+}
+
+/// Entries of `paths` that contain any of the home-relative `files`.
+pub fn paths_containing(paths: &[String], files: &[PathBuf]) -> Result<Vec<String>> {
+    let home = home_dir()?;
+    let mut found: Vec<String> = Vec::new();
+    for file in files {
+        if let Some(raw) = tracked_path_containing(paths, &home.join(file))?
+            && !found.contains(&raw)
+        {
+            found.push(raw);
+        }
+    }
+    Ok(found)
+}
+
+/// Where the tracked path `raw` lives relative to `$HOME`, which is also its place in a snapshot.
+pub fn home_relative(raw: &str) -> Result<PathBuf> {
+    relative_to_home(&expand_path(raw)?)
+}
+
+/// Expand a tracked path and make sure it lives under `$HOME`.
+pub fn resolve_tracked_path(raw: &str) -> Result<PathBuf> {
+    let expanded = expand_path(raw)?;
+    relative_to_home(&expanded)?;
+    Ok(expanded)
+}
+
+/// Write a path under `$HOME` as `~/…` so configs stay portable between machines.
+pub fn tilde_path(raw: &str) -> String {
+    let Ok(home) = home_dir() else {
+        return raw.to_string();
+    };
+    match Path::new(raw).strip_prefix(&home) {
+        Ok(rel) if !rel.as_os_str().is_empty() => format!("~/{}", rel.display()),
+        _ => raw.to_string(),
+    }
+}
+
+/// Symlinks recorded in `~/.config/omarchy-syncd/symlinks.json` that are missing or dangling.
+pub fn broken_symlinks() -> Result<Vec<String>> {
+    let meta_path = user_metadata_path()?;
+    if !meta_path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read_to_string(&meta_path)
+        .with_context(|| format!("Failed reading symlink metadata {}", meta_path.display()))?;
+    let entries: Vec<SymlinkEntry> =
+        serde_json::from_str(&data).with_context(|| "Failed parsing symlink metadata")?;
+    let home = home_dir()?;
+    let mut broken = Vec::new();
+    for entry in entries {
+        let dest = home.join(&entry.path);
+        match fs::symlink_metadata(&dest) {
+            Err(_) => broken.push(format!("~/{} is missing", entry.path)),
+            Ok(meta) if !meta.file_type().is_symlink() => {
+                broken.push(format!("~/{} is no longer a symlink", entry.path))
+            }
+            Ok(_) if !dest.exists() => broken.push(format!(
+                "~/{} points to missing {}",
+                entry.path, entry.target
+            )),
+            Ok(_) => {}
+        }
+    }
+    Ok(broken)
+}
+
+/// Cheap summary of a tracked path, used to notice local edits without reading contents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Number of files (and symlinks) under the path.
+    pub files: usize,
+    /// Newest modification time among them, in milliseconds since the Unix epoch.
+    pub newest: u64,
+}
+
+/// Modification time of the tracked path `raw` itself (not its contents), in milliseconds
+/// since the Unix epoch, or `None` if it does not exist on this machine.
+pub fn modified_time(raw: &str) -> Result<Option<u64>> {
+    let expanded = expand_path(raw)?;
+    let Ok(metadata) = fs::metadata(&expanded).or_else(|_| fs::symlink_metadata(&expanded)) else {
+        return Ok(None);
+    };
+    Ok(Some(millis(&metadata)))
+}
+
+fn millis(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |age| age.as_millis() as u64)
+}
+
+/// Fingerprint the tracked path `raw`, or `None` if it does not exist on this machine.
+pub fn fingerprint(raw: &str) -> Result<Option<Fingerprint>> {
+    let expanded = expand_path(raw)?;
+    if fs::symlink_metadata(&expanded).is_err() {
+        return Ok(None);
+    }
+    let mut print = Fingerprint::default();
+    let walker = WalkDir::new(&expanded)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        print.files += 1;
+        // Follow links so edits made through a linked path still count.
+        let metadata = match fs::metadata(entry.path()) {
+            Ok(metadata) => metadata,
+            Err(_) => entry.metadata()?,
+        };
+        print.newest = print.newest.max(millis(&metadata));
+    }
+    Ok(Some(print))
+}
+
+/// Compare two trees by structure and file contents.
+///
+/// Symlinks and `.git` directories are skipped since snapshots never contain them, as is
+/// any entry named in `ignore`.
+pub fn trees_equal(a: &Path, b: &Path, ignore: &[&str]) -> Result<bool> {
+    let left = list_tree(a, ignore)?;
+    let right = list_tree(b, ignore)?;
+    if left != right {
+        return Ok(false);
+    }
+    for (rel, is_dir) in &left {
+        if !is_dir && !files_equal(&a.join(rel), &b.join(rel))? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn list_tree(root: &Path, ignore: &[&str]) -> Result<Vec<(PathBuf, bool)>> {
+    let mut entries = Vec::new();
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name();
+            name != ".git" && !ignore.iter().any(|ignored| name == *ignored)
+        });
+    for entry in walker {
+        let entry = entry?;
+        if entry.path_is_symlink() {
+            continue;
+        }
+        let rel = entry
+            .path()
+            .strip_prefix(root)
+            .map(Path::to_path_buf)
+            .with_context(|| format!("Failed relativising {}", entry.path().display()))?;
+        entries.push((rel, entry.file_type().is_dir()));
+    }
+    Ok(entries)
+}
+
+fn files_equal(a: &Path, b: &Path) -> Result<bool> {
+    let meta_a = fs::metadata(a).with_context(|| format!("Failed to inspect {}", a.display()))?;
+    let meta_b = fs::metadata(b).with_context(|| format!("Failed to inspect {}", b.display()))?;
+    if meta_a.len() != meta_b.len() {
+        return Ok(false);
+    }
+    let mut buf_a = Vec::new();
+    let mut buf_b = Vec::new();
+    File::open(a)
+        .and_then(|mut file| file.read_to_end(&mut buf_a))
+        .with_context(|| format!("Failed reading {}", a.display()))?;
+    File::open(b)
+        .and_then(|mut file| file.read_to_end(&mut buf_b))
+        .with_context(|| format!("Failed reading {}", b.display()))?;
+    Ok(buf_a == buf_b)
 }
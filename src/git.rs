@@ -1,23 +1,219 @@
+use std::fmt;
 use std::fs;
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::{Context, Result};
 
+use crate::selector;
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Echo the raw output of every git invocation to stderr.
+pub fn set_verbose(enabled: bool) {
+    VERBOSE.store(enabled, Ordering::Relaxed);
+}
+
+fn verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Broad category of a failed git invocation, derived from its stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitErrorKind {
+    AuthFailed,
+    HostUnreachable,
+    BranchMissing,
+    NonFastForward,
+    RepoNotFound,
+    DiskFull,
+    Other,
+}
+
+impl GitErrorKind {
+    fn classify(stderr: &str) -> Self {
+        let text = stderr.to_lowercase();
+        let matches = |needles: &[&str]| needles.iter().any(|needle| text.contains(needle));
+
+        if matches(&["no space left on device", "disk quota exceeded"]) {
+            GitErrorKind::DiskFull
+        } else if matches(&[
+            "permission denied (publickey",
+            "authentication failed",
+            "could not read username",
+            "could not read password",
+            "host key verification failed",
+            "invalid username or password",
+        ]) {
+            GitErrorKind::AuthFailed
+        } else if matches(&[
+            "could not resolve host",
+            "could not resolve hostname",
+            "connection refused",
+            "connection timed out",
+            "operation timed out",
+            "network is unreachable",
+            "no route to host",
+            "failed to connect",
+        ]) {
+            GitErrorKind::HostUnreachable
+        } else if matches(&[
+            "repository not found",
+            "does not appear to be a git repository",
+        ]) || missing_repository(&text)
+        {
+            GitErrorKind::RepoNotFound
+        } else if matches(&["non-fast-forward", "fetch first", "updates were rejected"]) {
+            GitErrorKind::NonFastForward
+        } else if matches(&[
+            "not found in upstream",
+            "couldn't find remote ref",
+            "src refspec",
+            "did not match any file(s) known to git",
+        ]) {
+            GitErrorKind::BranchMissing
+        } else {
+            GitErrorKind::Other
+        }
+    }
+
+    /// Stable identifier used in JSON output.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GitErrorKind::AuthFailed => "auth_failed",
+            GitErrorKind::HostUnreachable => "host_unreachable",
+            GitErrorKind::BranchMissing => "branch_missing",
+            GitErrorKind::NonFastForward => "non_fast_forward",
+            GitErrorKind::RepoNotFound => "repo_not_found",
+            GitErrorKind::DiskFull => "disk_full",
+            GitErrorKind::Other => "other",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            GitErrorKind::AuthFailed => "authentication with the remote failed",
+            GitErrorKind::HostUnreachable => "the remote host could not be reached",
+            GitErrorKind::BranchMissing => "the branch does not exist on the remote",
+            GitErrorKind::NonFastForward => "the remote rejected a non-fast-forward push",
+            GitErrorKind::RepoNotFound => "the repository could not be found",
+            GitErrorKind::DiskFull => "the disk is full",
+            GitErrorKind::Other => "git reported an error",
+        }
+    }
+
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            GitErrorKind::AuthFailed => Some(
+                "Check your SSH agent (`ssh-add -l`) or Git credential helper and that your account can access the repository.",
+            ),
+            GitErrorKind::HostUnreachable => {
+                Some("Check your network connection and that the remote host is reachable.")
+            }
+            GitErrorKind::BranchMissing => {
+                Some("Check `repo.branch` in config.toml or create the branch on the remote first.")
+            }
+            GitErrorKind::NonFastForward => Some(
+                "Another machine pushed to the branch in the meantime; rerun the backup to build on the latest snapshot.",
+            ),
+            GitErrorKind::RepoNotFound => Some(
+                "Check `repo.url` in config.toml and that the repository exists and is accessible.",
+            ),
+            GitErrorKind::DiskFull => {
+                Some("Free up disk space (clones are created under $TMPDIR) and retry.")
+            }
+            GitErrorKind::Other => None,
+        }
+    }
+}
+
+/// git's "fatal: repository '<url>' does not exist" (local paths) or "... not found" (HTTP).
+fn missing_repository(text: &str) -> bool {
+    text.lines().any(|line| {
+        line.strip_prefix("fatal: repository '")
+            .is_some_and(|rest| rest.ends_with("' does not exist") || rest.ends_with("' not found"))
+    })
+}
+
+/// A failed git invocation with its captured output.
+#[derive(Debug)]
+pub struct GitError {
+    pub kind: GitErrorKind,
+    pub command: String,
+    pub code: Option<i32>,
+    pub stderr: String,
+}
+
+impl GitError {
+    fn from_output(args: &[&str], output: &Output) -> Self {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Self {
+            kind: GitErrorKind::classify(&stderr),
+            command: format!("git {}", args.join(" ")),
+            code: output.status.code(),
+            stderr,
+        }
+    }
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.command, self.kind.description())?;
+        if self.kind == GitErrorKind::Other {
+            write!(f, " (exit status {:?})", self.code)?;
+        }
+        if let Some(hint) = self.kind.hint() {
+            write!(f, "\nhint: {hint}")?;
+        }
+        if self.kind == GitErrorKind::Other
+            && let Some(last) = self
+                .stderr
+                .lines()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+        {
+            write!(f, "\n{}", last.trim())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GitError {}
+
+/// Whether `err` was caused by the remote being unreachable (e.g. no network).
+pub fn is_unreachable(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<GitError>()
+        .is_some_and(|git_err| git_err.kind == GitErrorKind::HostUnreachable)
+}
+
+/// The git failure behind `err`, if any, looking through added context.
+pub fn find_error(err: &anyhow::Error) -> Option<&GitError> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<GitError>())
+}
+
 pub fn clone_repo(url: &str, branch: &str, repo_dir: &Path) -> Result<()> {
     if repo_dir.exists() {
         fs::remove_dir_all(repo_dir)
             .with_context(|| format!("Failed cleaning repo directory {}", repo_dir.display()))?;
     }
 
-    let clone_with_branch = Command::new("git")
-        .args(["clone", "--branch", branch, "--single-branch", url])
-        .arg(repo_dir)
-        .status()
-        .context("Failed to execute git clone")?;
-
-    if clone_with_branch.success() {
-        return Ok(());
+    let repo_arg = repo_dir.to_string_lossy();
+    match run_git_in(
+        None,
+        &[
+            "clone",
+            "--branch",
+            branch,
+            "--single-branch",
+            url,
+            &repo_arg,
+        ],
+    ) {
+        Ok(_) => return Ok(()),
+        Err(err) if err.kind == GitErrorKind::BranchMissing => {}
+        Err(err) => return Err(err.into()),
     }
 
     if repo_dir.exists() {
@@ -25,15 +221,7 @@ pub fn clone_repo(url: &str, branch: &str, repo_dir: &Path) -> Result<()> {
             .with_context(|| format!("Failed cleaning repo directory {}", repo_dir.display()))?;
     }
 
-    let fallback_clone = Command::new("git")
-        .args(["clone", url])
-        .arg(repo_dir)
-        .status()
-        .context("Failed to execute fallback git clone")?;
-
-    if !fallback_clone.success() {
-        anyhow::bail!("git clone exited with status {}", fallback_clone);
-    }
+    run_git_in(None, &["clone", url, &repo_arg])?;
 
     if run_git(repo_dir, &["checkout", branch]).is_err() {
         run_git(repo_dir, &["checkout", "-b", branch])?;
@@ -42,82 +230,289 @@ pub fn clone_repo(url: &str, branch: &str, repo_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-pub fn commit_and_push(repo_dir: &Path, message: &str, branch: &str) -> Result<()> {
+/// Clone the default branch of `source`, which may be a URL or a local (possibly bare) repository.
+pub fn clone_default_branch(source: &str, repo_dir: &Path) -> Result<()> {
+    let repo_arg = repo_dir.to_string_lossy();
+    run_git_in(None, &["clone", "--quiet", source, &repo_arg])?;
+    Ok(())
+}
+
+pub fn update(repo_dir: &Path, branch: &str) -> Result<()> {
+    run_git(repo_dir, &["reset", "--hard", "--quiet"])?;
+    run_git(repo_dir, &["clean", "-fd", "--quiet"])?;
+    match run_git(repo_dir, &["fetch", "origin", branch]) {
+        Ok(_) => {}
+        // Nothing to rebase onto yet (e.g. the first backup into an empty remote).
+        Err(err) if err.kind == GitErrorKind::BranchMissing => return Ok(()),
+        Err(err) => return Err(err.into()),
+    }
+
+    let upstream = format!("origin/{branch}");
+    if !has_ref(repo_dir, &upstream) {
+        return Ok(());
+    }
+
+    if pending_commits(repo_dir, branch)? == 0 {
+        run_git(repo_dir, &["reset", "--hard", "--quiet", &upstream])?;
+        return Ok(());
+    }
+
+    if let Err(err) = run_git(repo_dir, &["rebase", &upstream]) {
+        let _ = run_git(repo_dir, &["rebase", "--abort"]);
+        return Err(anyhow::Error::new(err).context(format!(
+            "Unpushed snapshots in {} conflict with {upstream}",
+            repo_dir.display()
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn commit(repo_dir: &Path, message: &str) -> Result<bool> {
     run_git(repo_dir, &["add", "--all", "."])?;
     clean_gitlinks(repo_dir)?;
 
-    let commit_status = Command::new("git")
-        .current_dir(repo_dir)
-        .args(["commit", "-m", message])
-        .status()
-        .context("Failed to execute git commit")?;
-
-    if !commit_status.success() {
-        if commit_status.code() == Some(1) {
-            println!("No changes to commit.");
-            return Ok(());
-        } else {
-            anyhow::bail!("git commit failed with status {:?}", commit_status.code());
+    if let Err(err) = run_git(repo_dir, &["commit", "-m", message]) {
+        if err.code == Some(1) && err.kind == GitErrorKind::Other {
+            return Ok(false);
         }
+        return Err(err.into());
+    }
+
+    Ok(true)
+}
+
+pub fn push(repo_dir: &Path, branch: &str) -> Result<()> {
+    run_git(
+        repo_dir,
+        &["push", "origin", &format!("HEAD:refs/heads/{branch}")],
+    )?;
+    // Keep origin/<branch> current so the pending count drops without another fetch.
+    run_git(
+        repo_dir,
+        &[
+            "update-ref",
+            &format!("refs/remotes/origin/{branch}"),
+            "HEAD",
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn local_edits(repo_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !has_ref(repo_dir, "HEAD") {
+        return Ok(Vec::new());
     }
+    let output = run_git(
+        repo_dir,
+        &[
+            "ls-files",
+            "-z",
+            "--modified",
+            "--deleted",
+            "--others",
+            "--exclude-standard",
+        ],
+    )?;
+    let mut edits: Vec<PathBuf> = output
+        .stdout
+        .split(|byte| *byte == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| PathBuf::from(String::from_utf8_lossy(name).into_owned()))
+        .collect();
+    // Deleted files are also reported as modified.
+    edits.sort();
+    edits.dedup();
+    Ok(edits)
+}
 
-    run_git(repo_dir, &["push", "origin", branch])?;
+pub fn stash(repo_dir: &Path) -> Result<bool> {
+    if local_edits(repo_dir)?.is_empty() {
+        return Ok(false);
+    }
+    run_git(
+        repo_dir,
+        &[
+            "stash",
+            "push",
+            "--quiet",
+            "--include-untracked",
+            "-m",
+            "omarchy-syncd local edits",
+        ],
+    )?;
+    Ok(true)
+}
 
+pub fn unstash(repo_dir: &Path) -> Result<()> {
+    run_git(repo_dir, &["stash", "pop", "--quiet"]).map_err(|err| {
+        anyhow::Error::new(err).context(format!(
+            "Local edits in {} conflict with the latest snapshot; resolve them with `git -C {} status`",
+            repo_dir.display(),
+            repo_dir.display()
+        ))
+    })?;
     Ok(())
 }
 
+pub fn pending_commits(repo_dir: &Path, branch: &str) -> Result<usize> {
+    if !has_ref(repo_dir, "HEAD") {
+        return Ok(0);
+    }
+    let upstream = format!("origin/{branch}");
+    let range = if has_ref(repo_dir, &upstream) {
+        format!("{upstream}..HEAD")
+    } else {
+        "HEAD".to_string()
+    };
+    let output = run_git(repo_dir, &["rev-list", "--count", &range])?;
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .context("git rev-list --count produced unexpected output")
+}
+
+pub fn remote_url(repo_dir: &Path) -> Result<Option<String>> {
+    // Read the raw config value; `git remote get-url` would expand `insteadOf` rewrites.
+    match run_git(repo_dir, &["config", "--get", "remote.origin.url"]) {
+        Ok(output) => Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+pub fn current_branch(repo_dir: &Path) -> Result<Option<String>> {
+    match run_git(repo_dir, &["symbolic-ref", "--short", "HEAD"]) {
+        Ok(output) => Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+pub fn head_commit(repo_dir: &Path) -> Result<Option<String>> {
+    match run_git(repo_dir, &["rev-parse", "--verify", "--quiet", "HEAD"]) {
+        Ok(output) => Ok(Some(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+fn has_ref(repo_dir: &Path, name: &str) -> bool {
+    run_git(repo_dir, &["rev-parse", "--verify", "--quiet", name]).is_ok()
+}
+
 pub fn verify_remote(url: &str, branch: &str) -> Result<()> {
-    let status = Command::new("git")
-        .args(["ls-remote", "--exit-code", url, branch])
-        .status()
-        .context("Failed to execute git ls-remote")?;
-
-    if !status.success() {
-        anyhow::bail!(
-            "git ls-remote could not find branch '{}' on {}",
-            branch,
-            url
-        );
+    match run_git_in(None, &["ls-remote", "--exit-code", url, branch]) {
+        Ok(_) => Ok(()),
+        // `--exit-code` exits with 2 when the remote answered but had no matching ref.
+        Err(mut err) if err.code == Some(2) && err.kind == GitErrorKind::Other => {
+            err.kind = GitErrorKind::BranchMissing;
+            Err(anyhow::Error::new(err).context(format!(
+                "git ls-remote could not find branch '{}' on {}",
+                branch, url
+            )))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn bundle_history(repo_dir: &Path, branch: &str, out: &Path) -> Result<()> {
+    if !has_ref(repo_dir, "HEAD") {
+        anyhow::bail!("Nothing to export yet. Run 'omarchy-syncd backup' first.");
+    }
+    let out_arg = out.to_string_lossy();
+    run_git(repo_dir, &["bundle", "create", &out_arg, "HEAD", branch])?;
+    Ok(())
+}
+
+pub fn bundle_snapshot(worktree: &Path, branch: &str, out: &Path) -> Result<()> {
+    let staging = tempfile::tempdir().context("Failed to create temporary directory")?;
+    let git_dir = staging.path().join("export.git");
+    let git_dir_arg = format!("--git-dir={}", git_dir.display());
+    let work_tree_arg = format!("--work-tree={}", worktree.display());
+    let git_dir_path = git_dir.to_string_lossy();
+
+    run_git_in(
+        None,
+        &["init", "--quiet", "--bare", "-b", branch, &git_dir_path],
+    )?;
+    run_git_in(None, &[&git_dir_arg, &work_tree_arg, "add", "--all", "."])?;
+    if let Err(err) = run_git_in(
+        None,
+        &[
+            &git_dir_arg,
+            &work_tree_arg,
+            "commit",
+            "--quiet",
+            "-m",
+            "omarchy-syncd snapshot export",
+        ],
+    ) {
+        if err.code == Some(1) && err.kind == GitErrorKind::Other {
+            anyhow::bail!("Nothing to export yet. Run 'omarchy-syncd backup' first.");
+        }
+        return Err(err.into());
     }
 
+    let out_arg = out.to_string_lossy();
+    run_git_in(
+        None,
+        &[&git_dir_arg, "bundle", "create", &out_arg, "HEAD", branch],
+    )?;
     Ok(())
 }
 
 fn clean_gitlinks(repo_dir: &Path) -> Result<()> {
-    let output = Command::new("git")
-        .current_dir(repo_dir)
-        .args(["ls-files", "--stage"])
-        .output()
-        .context("Failed to list staged files")?;
+    let output = run_git(repo_dir, &["ls-files", "--stage"])?;
     let listing =
         String::from_utf8(output.stdout).context("git ls-files produced invalid UTF-8 output")?;
 
     for line in listing.lines() {
-        if let Some((prefix, path)) = line.split_once('\t') {
-            if prefix.starts_with("160000 ") {
-                run_git(repo_dir, &["rm", "--cached", path])?;
-                run_git(repo_dir, &["add", "--force", "--all", path])?;
-            }
+        if let Some((prefix, path)) = line.split_once('\t')
+            && prefix.starts_with("160000 ")
+        {
+            run_git(repo_dir, &["rm", "--cached", path])?;
+            run_git(repo_dir, &["add", "--force", "--all", path])?;
         }
     }
 
     Ok(())
 }
 
-fn run_git(repo_dir: &Path, args: &[&str]) -> Result<()> {
-    let status = Command::new("git")
-        .current_dir(repo_dir)
-        .args(args)
-        .status()
-        .with_context(|| format!("Failed running git {:?}", args))?;
+fn run_git(repo_dir: &Path, args: &[&str]) -> std::result::Result<Output, GitError> {
+    run_git_in(Some(repo_dir), args)
+}
+
+fn run_git_in(dir: Option<&Path>, args: &[&str]) -> std::result::Result<Output, GitError> {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    if selector::is_non_interactive() {
+        // Fail instead of asking for credentials or host keys nobody can type in.
+        cmd.env("GIT_TERMINAL_PROMPT", "0").stdin(Stdio::null());
+        if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+            cmd.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+        }
+    }
+    let output = cmd.args(args).output().map_err(|err| GitError {
+        kind: GitErrorKind::Other,
+        command: format!("git {}", args.join(" ")),
+        code: None,
+        stderr: format!("Failed to execute git: {err}"),
+    })?;
 
-    if !status.success() {
-        anyhow::bail!(
-            "git command {:?} exited with status {:?}",
-            args,
-            status.code()
-        );
+    if verbose() {
+        eprint!("{}", String::from_utf8_lossy(&output.stdout));
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
     }
 
-    Ok(())
+    if !output.status.success() {
+        return Err(GitError::from_output(args, &output));
+    }
+
+    Ok(output)
 }
@@ -0,0 +1,172 @@
+use std::{
+    path::Path,
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use which::which;
+
+use crate::bundles;
+use crate::config::HooksConfig;
+use crate::output;
+use crate::say;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What to do when a hook exits non-zero or times out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailurePolicy {
+    /// Stop the operation and report the failure.
+    Abort,
+    /// Print the failure and carry on.
+    #[default]
+    Warn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreBackup,
+    PostBackup,
+    PreRestore,
+    PostRestore,
+}
+
+impl HookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::PreBackup => "pre_backup",
+            HookEvent::PostBackup => "post_backup",
+            HookEvent::PreRestore => "pre_restore",
+            HookEvent::PostRestore => "post_restore",
+        }
+    }
+
+    fn commands(self, cfg: &HooksConfig) -> &[String] {
+        match self {
+            HookEvent::PreBackup => &cfg.pre_backup,
+            HookEvent::PostBackup => &cfg.post_backup,
+            HookEvent::PreRestore => &cfg.pre_restore,
+            HookEvent::PostRestore => &cfg.post_restore,
+        }
+    }
+}
+
+/// Details exported to hooks as `OMARCHY_SYNCD_*` environment variables.
+pub struct HookContext<'a> {
+    /// Tracked paths the operation covers before it runs, or changed once it is done.
+    pub paths: &'a [String],
+    /// Configured bundles with at least one path in `paths`.
+    pub bundles: &'a [String],
+}
+
+/// Run every command configured for `event` through `sh -c`, in order.
+pub fn run(cfg: &HooksConfig, event: HookEvent, ctx: &HookContext) -> Result<()> {
+    for command in event.commands(cfg) {
+        if let Err(err) = run_one(cfg, event, command, ctx) {
+            handle_failure(cfg, &format!("{} hook", event.as_str()), err)?;
+        }
+    }
+    Ok(())
+}
+
+/// Run the built-in post-restore actions of each bundle in `changed_bundles`.
+///
+/// Actions whose program is not installed are skipped, so Hyprland-specific reloads are
+/// harmless elsewhere.
+pub fn run_bundle_actions(
+    cfg: &HooksConfig,
+    changed_bundles: &[String],
+    changed_paths: &[String],
+) -> Result<()> {
+    if !cfg.bundle_actions {
+        return Ok(());
+    }
+    for id in changed_bundles {
+        let Some(bundle) = bundles::find(id) else {
+            continue;
+        };
+        let actions: Vec<&str> = bundle
+            .post_restore
+            .iter()
+            .copied()
+            .filter(|action| {
+                action
+                    .split_whitespace()
+                    .next()
+                    .is_some_and(|program| which(program).is_ok())
+            })
+            .collect();
+        if actions.is_empty() {
+            continue;
+        }
+
+        say!("Reloading {}...", bundle.name);
+        let paths: Vec<String> = changed_paths
+            .iter()
+            .filter(|path| {
+                bundle
+                    .paths
+                    .iter()
+                    .any(|bundle_path| Path::new(path).starts_with(bundle_path))
+            })
+            .cloned()
+            .collect();
+        let ids = [id.clone()];
+        let ctx = HookContext {
+            paths: &paths,
+            bundles: &ids,
+        };
+        for action in actions {
+            if let Err(err) = run_one(cfg, HookEvent::PostRestore, action, &ctx) {
+                handle_failure(cfg, &format!("{} reload action", bundle.id), err)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_failure(cfg: &HooksConfig, label: &str, err: anyhow::Error) -> Result<()> {
+    match cfg.on_failure {
+        FailurePolicy::Abort => Err(err.context(format!("{label} failed"))),
+        FailurePolicy::Warn => {
+            say!("{label} failed; continuing: {err:#}");
+            Ok(())
+        }
+    }
+}
+
+fn run_one(cfg: &HooksConfig, event: HookEvent, command: &str, ctx: &HookContext) -> Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("OMARCHY_SYNCD_HOOK", event.as_str())
+        .env("OMARCHY_SYNCD_PATHS", ctx.paths.join("\n"))
+        .env("OMARCHY_SYNCD_BUNDLES", ctx.bundles.join(" "))
+        .stdout(output::child_stdout())
+        .spawn()
+        .with_context(|| format!("Failed to start hook `{command}`"))?;
+
+    let timeout = Duration::from_secs(cfg.timeout);
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("Failed waiting for hook `{command}`"))?
+        {
+            if !status.success() {
+                anyhow::bail!("`{command}` exited with status {:?}", status.code());
+            }
+            return Ok(());
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("`{command}` timed out after {}s", cfg.timeout);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use tempfile::{TempDir, tempdir};
+use walkdir::WalkDir;
+
+use crate::git;
+use crate::output::Skipped;
+use crate::say;
+
+/// Layouts used by other dotfile managers that `import` understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportSource {
+    /// GNU stow directory: one package per subdirectory, each mirroring $HOME.
+    Stow,
+    /// chezmoi source directory, with `dot_`, `private_` and `executable_` names.
+    Chezmoi,
+    /// Repository whose work tree is $HOME, as used with `git --bare` or yadm.
+    Bare,
+}
+
+impl ImportSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImportSource::Stow => "stow",
+            ImportSource::Chezmoi => "chezmoi",
+            ImportSource::Bare => "bare",
+        }
+    }
+}
+
+/// Dotfiles mapped to home-relative paths and staged in a temporary directory.
+pub struct Import {
+    staged: TempDir,
+    /// Imported files, relative to $HOME.
+    pub files: Vec<PathBuf>,
+    /// Entries that could not be imported, and why.
+    pub skipped: Vec<Skipped>,
+}
+
+impl Import {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            staged: tempdir().context("Failed to create temporary directory")?,
+            files: Vec::new(),
+            skipped: Vec::new(),
+        })
+    }
+
+    fn skip(&mut self, rel: &Path, reason: impl Into<String>) {
+        let path = format!("~/{}", rel.display());
+        let reason = reason.into();
+        say!("Skipping {path} because {reason}.");
+        self.skipped.push(Skipped { path, reason });
+    }
+
+    /// Copy `source` to `rel` in the staging area, applying `mode` when given.
+    fn place(&mut self, source: &Path, rel: &Path, mode: Option<u32>) -> Result<()> {
+        let dest = self.staged.path().join(rel);
+        if dest.exists() {
+            self.skip(rel, "another entry already maps to it");
+            return Ok(());
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed creating directory {}", parent.display()))?;
+        }
+        fs::copy(source, &dest).with_context(|| format!("Failed copying {}", source.display()))?;
+        if let Some(mode) = mode {
+            set_mode(&dest, mode)?;
+        }
+        self.files.push(rel.to_path_buf());
+        Ok(())
+    }
+
+    /// Paths to track, as `~/…`: whole `~/.config/<app>` directories, single files elsewhere.
+    pub fn tracked_paths(&self) -> Vec<String> {
+        self.units()
+            .iter()
+            .map(|unit| format!("~/{}", unit.display()))
+            .collect()
+    }
+
+    fn units(&self) -> BTreeSet<PathBuf> {
+        self.files
+            .iter()
+            .map(|rel| {
+                let mut components = rel.components();
+                match (components.next(), components.next(), components.next()) {
+                    (Some(config), Some(app), Some(_)) if config.as_os_str() == ".config" => {
+                        Path::new(".config").join(app)
+                    }
+                    _ => rel.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Copy the staged files into a snapshot, replacing whatever it held at each tracked path.
+    pub fn write_snapshot(&self, repo_dir: &Path) -> Result<()> {
+        for unit in self.units() {
+            let dest = repo_dir.join(&unit);
+            if dest.is_dir() {
+                fs::remove_dir_all(&dest)
+                    .with_context(|| format!("Failed removing {}", dest.display()))?;
+            } else if dest.exists() {
+                fs::remove_file(&dest)
+                    .with_context(|| format!("Failed removing {}", dest.display()))?;
+            }
+        }
+        for rel in &self.files {
+            let dest = repo_dir.join(rel);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed creating directory {}", parent.display()))?;
+            }
+            fs::copy(self.staged.path().join(rel), &dest)
+                .with_context(|| format!("Failed writing {}", dest.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the dotfiles in `source` (a directory or git URL) laid out as `kind`.
+pub fn load(kind: ImportSource, source: &str) -> Result<Import> {
+    let expanded = PathBuf::from(shellexpand::tilde(source).into_owned());
+    let mut checkout: Option<TempDir> = None;
+    let root = if kind != ImportSource::Bare && expanded.is_dir() {
+        expanded
+    } else {
+        // Bare repos are always cloned: their files only exist as git objects.
+        let temp = tempdir().context("Failed to create temporary directory")?;
+        let dest = temp.path().join("source");
+        git::clone_default_branch(source, &dest)?;
+        checkout = Some(temp);
+        dest
+    };
+
+    let mut import = Import::new()?;
+    match kind {
+        ImportSource::Stow => import_stow(&mut import, &root)?,
+        ImportSource::Chezmoi => import_chezmoi(&mut import, &root, Path::new(""))?,
+        ImportSource::Bare => import_tree(&mut import, &root, |name| name.to_string())?,
+    }
+    drop(checkout);
+
+    if import.files.is_empty() {
+        bail!("Found no dotfiles to import in {source}");
+    }
+    Ok(import)
+}
+
+/// Every top-level directory is a package; `dot-` names are renamed as `stow --dotfiles` does.
+fn import_stow(import: &mut Import, root: &Path) -> Result<()> {
+    for package in sorted_entries(root)? {
+        let name = package.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || !package.file_type()?.is_dir() {
+            continue;
+        }
+        import_tree(import, &package.path(), |name| {
+            match name.strip_prefix("dot-") {
+                Some(rest) => format!(".{rest}"),
+                None => name.to_string(),
+            }
+        })?;
+    }
+    Ok(())
+}
+
+/// Copy every file under `root`, mapping each path component through `rename`.
+fn import_tree(import: &mut Import, root: &Path, rename: impl Fn(&str) -> String) -> Result<()> {
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let rel: PathBuf = entry
+            .path()
+            .strip_prefix(root)
+            .with_context(|| format!("Failed relativising {}", entry.path().display()))?
+            .components()
+            .map(|component| match component {
+                Component::Normal(name) => rename(&name.to_string_lossy()),
+                other => other.as_os_str().to_string_lossy().into_owned(),
+            })
+            .collect();
+        if entry.file_type().is_symlink() {
+            import.skip(&rel, "it is a symlink");
+            continue;
+        }
+        import.place(entry.path(), &rel, None)?;
+    }
+    Ok(())
+}
+
+/// Walk a chezmoi source directory, translating its name attributes into paths and modes.
+fn import_chezmoi(import: &mut Import, dir: &Path, rel: &Path) -> Result<()> {
+    for entry in sorted_entries(dir)? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // chezmoi ignores dot files in its source directory; they hold its own state.
+        if name.starts_with('.') {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        let attrs = ChezmoiName::parse(&name);
+        let target = rel.join(&attrs.name);
+        if file_type.is_dir() {
+            if attrs.remove {
+                import.skip(&target, "chezmoi only removes it");
+                continue;
+            }
+            import_chezmoi(import, &entry.path(), &target)?;
+            let staged = import.staged.path().join(&target);
+            if attrs.private && staged.is_dir() {
+                set_mode(&staged, 0o700)?;
+            }
+        } else if !file_type.is_file() {
+            import.skip(&target, "it is a symlink");
+        } else if let Some(reason) = attrs.unsupported() {
+            import.skip(&target, reason);
+        } else {
+            import.place(&entry.path(), &target, Some(attrs.mode()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Target name and attributes encoded in a chezmoi source file or directory name.
+#[derive(Debug, Default)]
+struct ChezmoiName {
+    name: String,
+    private: bool,
+    readonly: bool,
+    executable: bool,
+    template: bool,
+    encrypted: bool,
+    script: bool,
+    modify: bool,
+    remove: bool,
+    symlink: bool,
+}
+
+impl ChezmoiName {
+    fn parse(raw: &str) -> Self {
+        let mut attrs = Self::default();
+        let mut name = raw;
+        loop {
+            // `dot_` and `literal_` end the prefix list; whatever follows is the name.
+            if let Some(rest) = name.strip_prefix("dot_") {
+                attrs.name = format!(".{rest}");
+                break;
+            }
+            if let Some(rest) = name.strip_prefix("literal_") {
+                attrs.name = rest.to_string();
+                break;
+            }
+            let Some((prefix, rest)) = name.split_once('_') else {
+                attrs.name = name.to_string();
+                break;
+            };
+            match prefix {
+                "private" => attrs.private = true,
+                "readonly" => attrs.readonly = true,
+                "executable" => attrs.executable = true,
+                "encrypted" => attrs.encrypted = true,
+                "modify" => attrs.modify = true,
+                "remove" => attrs.remove = true,
+                "symlink" => attrs.symlink = true,
+                "run" => attrs.script = true,
+                // Only change when or how chezmoi applies the target, not what it contains.
+                "create" | "empty" | "exact" | "external" | "once" | "onchange" | "before"
+                | "after" => {}
+                _ => {
+                    attrs.name = name.to_string();
+                    break;
+                }
+            }
+            name = rest;
+        }
+        if let Some(stripped) = attrs.name.strip_suffix(".literal") {
+            attrs.name = stripped.to_string();
+        } else if let Some(stripped) = attrs.name.strip_suffix(".tmpl") {
+            attrs.name = stripped.to_string();
+            attrs.template = true;
+        }
+        attrs
+    }
+
+    fn unsupported(&self) -> Option<&'static str> {
+        if self.script {
+            Some("it is a chezmoi script")
+        } else if self.modify {
+            Some("it is a chezmoi modify script")
+        } else if self.remove {
+            Some("chezmoi only removes it")
+        } else if self.symlink {
+            Some("it is a chezmoi symlink")
+        } else if self.encrypted {
+            Some("it is encrypted")
+        } else if self.template {
+            Some("it is a chezmoi template; render it with `chezmoi cat` and add it by hand")
+        } else {
+            None
+        }
+    }
+
+    fn mode(&self) -> u32 {
+        let mut mode = if self.executable { 0o755 } else { 0o644 };
+        if self.private {
+            mode &= !0o077;
+        }
+        if self.readonly {
+            mode &= !0o222;
+        }
+        mode
+    }
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<fs::DirEntry>> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed reading {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("Failed reading {}", dir.display()))?;
+    entries.sort_by_key(|entry| entry.file_name());
+    Ok(entries)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed setting permissions on {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
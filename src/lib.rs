@@ -1,5 +1,18 @@
 pub mod bundles;
 pub mod config;
+pub mod config_edit;
+pub mod doctor;
 pub mod fs_ops;
 pub mod git;
+pub mod hooks;
+pub mod import;
+pub mod notify;
+pub mod output;
+pub mod packages;
+pub mod preview;
+pub mod schedule;
 pub mod selector;
+pub mod state;
+pub mod storage;
+pub mod validate;
+pub mod waybar;
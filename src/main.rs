@@ -1,21 +1,42 @@
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::env;
 use std::fs;
-use std::io::{self, IsTerminal, Write};
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand};
-use tempfile::tempdir;
+use serde::Serialize;
+use serde_json::{Value, json};
 use which::which;
 
 use omarchy_syncd::{
-    bundles, config, fs_ops, git,
-    selector::{self, Choice},
+    bundles, config,
+    config_edit::ConfigDocument,
+    doctor::{self, Status},
+    fs_ops::{self, DeployMode},
+    git,
+    hooks::{self, HookContext, HookEvent},
+    import::{self, ImportSource},
+    notify::{self, NotifySend, Outcome},
+    output::{self, FailedReport, OutputFormat, Skipped},
+    packages::{self, SystemRunner},
+    preview::Previewer,
+    say, schedule,
+    selector::{self, Choice, Menu, Selector, SelectorKind},
+    state::{self, SyncState},
+    storage::{self, Published, StorageKind, Workspace},
+    validate, waybar,
 };
 
-use config::{FileConfig, RepoConfig, SyncConfig, load_config, write_config};
+use config::{
+    FileConfig, HooksConfig, NotificationsConfig, PackagesConfig, RepoConfig, StorageConfig,
+    SyncConfig, UiConfig, load_config, write_config,
+};
 
 #[derive(Parser)]
 #[command(
@@ -25,16 +46,56 @@ use config::{FileConfig, RepoConfig, SyncConfig, load_config, write_config};
     propagate_version = true
 )]
 struct Cli {
+    /// Echo the raw output of git commands to stderr.
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    /// Print results as human-readable text or as a single JSON document.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    output: OutputFormat,
+    /// Read configuration from this file instead of ~/.config/omarchy-syncd/config.toml
+    /// (also settable with $OMARCHY_SYNCD_CONFIG).
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// How to pick from lists: skim in the terminal, a dmenu-style launcher, or a numbered
+    /// prompt (also settable with $OMARCHY_SYNCD_SELECTOR or `selector` under [ui]).
+    #[arg(long, value_enum, global = true, value_name = "KIND")]
+    selector: Option<SelectorKind>,
+    /// Never prompt; anything that needs input fails with exit status 3 instead
+    /// (also settable with OMARCHY_SYNCD_NONINTERACTIVE=1).
+    #[arg(long, global = true)]
+    non_interactive: bool,
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Clone the remote, copy dotfiles, commit, and push to GitHub.
+    /// Copy dotfiles into the configured storage (by default: commit and push to GitHub).
     Backup(BackupArgs),
-    /// Clone the remote and restore tracked files into $HOME.
+    /// Fetch the latest snapshot and restore tracked files into $HOME.
     Restore(RestoreArgs),
+    /// Write the backup history (or latest snapshot) to a git bundle file.
+    Export(ExportArgs),
+    /// Compare or install the package list recorded by backup.
+    #[command(subcommand)]
+    Packages(PackagesCommand),
+    /// Push snapshots that were queued while the remote was unreachable.
+    Push,
+    /// Show the configured storage and how many snapshots are waiting to be pushed.
+    Status,
+    /// Keep running and periodically push queued snapshots.
+    Daemon(DaemonArgs),
+    /// Manage a systemd user timer that runs backups periodically.
+    #[command(subcommand)]
+    Schedule(ScheduleCommand),
+    /// Print sync status as JSON for a Waybar custom module.
+    Waybar,
+    /// Check git, the remote, config and tracked paths; exits non-zero if any check fails.
+    Doctor,
+    /// Set up a new machine from a backup repo: fetch its config, then restore.
+    Bootstrap(BootstrapArgs),
+    /// Track dotfiles kept with stow, chezmoi or a bare git repo and store a first snapshot.
+    Import(ImportArgs),
     /// Launch the interactive selector to choose bundles and dotfiles.
     Install(InstallArgs),
     /// Open the high-level omarchy-syncd menu.
@@ -45,6 +106,75 @@ enum Commands {
     Uninstall(UninstallArgs),
 }
 
+#[derive(Subcommand)]
+enum PackagesCommand {
+    /// List recorded packages that are not installed on this machine.
+    Diff,
+    /// Install recorded packages that are missing with pacman and an AUR helper.
+    Install,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Check the config file and report every problem with its line and column.
+    Validate,
+    /// Upgrade the config file to the current schema version, keeping a `.bak` copy.
+    Migrate(MigrateArgs),
+    /// Print the config file, or with --effective the settings in use after defaults and overrides.
+    Show {
+        #[arg(long)]
+        effective: bool,
+    },
+    /// Print a setting such as `repo.branch` (its default if the file does not set it).
+    Get { key: String },
+    /// Change a setting such as `repo.url`, keeping comments and formatting intact.
+    Set { key: String, value: String },
+    /// Track extra paths (no-op for paths already listed).
+    AddPath {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Stop tracking paths listed under [files].
+    RemovePath {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Enable bundles (no-op for bundles already listed).
+    AddBundle {
+        #[arg(required = true)]
+        bundles: Vec<String>,
+    },
+    /// Disable bundles.
+    RemoveBundle {
+        #[arg(required = true)]
+        bundles: Vec<String>,
+    },
+}
+
+#[derive(Args)]
+struct MigrateArgs {
+    /// Show the changes as a diff without writing anything.
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommand {
+    /// Install and start a timer that runs `backup --all --no-ui`.
+    Enable(ScheduleEnableArgs),
+    /// Show whether the backup timer is installed and running.
+    Status,
+    /// Stop the backup timer and remove its unit files.
+    Disable,
+}
+
+#[derive(Args)]
+struct ScheduleEnableArgs {
+    /// How often to back up (e.g. 30m, 1h, 1d).
+    #[arg(long, default_value = "1h", value_parser = parse_interval)]
+    every: Duration,
+}
+
 #[derive(Args)]
 struct BackupArgs {
     /// Commit message to use when pushing changes. Defaults to "Automated backup".
@@ -59,6 +189,9 @@ struct BackupArgs {
     /// Disable interactive selection even if running in a TTY.
     #[arg(long = "no-ui")]
     no_ui: bool,
+    /// List the individual files inside tracked directories in the selector.
+    #[arg(long)]
+    files: bool,
 }
 
 #[derive(Args)]
@@ -88,10 +221,60 @@ struct RestoreArgs {
     /// Disable interactive selection even if running in a TTY.
     #[arg(long = "no-ui")]
     no_ui: bool,
+    /// Restore from a bundle file written by `export` instead of the configured storage.
+    #[arg(long = "from-bundle")]
+    from_bundle: Option<PathBuf>,
+    /// List the individual files inside tracked directories in the selector; the snapshot
+    /// is fetched before the selector opens.
+    #[arg(long)]
+    files: bool,
 }
 
 #[derive(Args)]
+struct BootstrapArgs {
+    /// Git remote holding backups made by omarchy-syncd.
+    repo_url: String,
+    /// Branch the backups were pushed to.
+    #[arg(long, default_value = "main")]
+    branch: String,
+    /// Write the fetched config without asking for confirmation.
+    #[arg(long)]
+    yes: bool,
+    /// Restore every tracked path instead of choosing them interactively.
+    #[arg(long)]
+    all: bool,
+    /// Replace an existing config file.
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Args)]
+struct ImportArgs {
+    /// Dotfile manager whose layout `source` uses.
+    #[arg(long, value_enum)]
+    from: ImportSource,
+    /// Directory or git URL holding the dotfiles.
+    source: String,
+    /// Print the paths that would be tracked without touching the config or storage.
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    /// Bundle file to write.
+    #[arg(long)]
+    out: PathBuf,
+    /// Export only the latest snapshot instead of the full backup history.
+    #[arg(long)]
+    latest: bool,
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
 struct ConfigArgs {
+    #[command(subcommand)]
+    command: Option<ConfigCommand>,
     /// Launch the given editor instead of $EDITOR.
     #[arg(long)]
     editor: Option<String>,
@@ -102,11 +285,17 @@ struct ConfigArgs {
     #[arg(long = "create")]
     create: bool,
     /// Write a new configuration instead of opening the editor.
-    #[arg(long, requires = "repo_url")]
+    #[arg(long)]
     write: bool,
     /// Git remote URL to use when writing a configuration.
     #[arg(long = "repo-url", requires = "write")]
     repo_url: Option<String>,
+    /// Where snapshots are stored when writing a configuration.
+    #[arg(long, value_enum, default_value_t = StorageKind::Git, requires = "write")]
+    storage: StorageKind,
+    /// Directory used by the `directory` and `tarball` storage kinds.
+    #[arg(long = "storage-path", requires = "write")]
+    storage_path: Option<String>,
     /// Git branch to track when writing a configuration.
     #[arg(long, default_value = "main")]
     branch: String,
@@ -127,27 +316,168 @@ struct ConfigArgs {
     force: bool,
 }
 
+#[derive(Args)]
+struct DaemonArgs {
+    /// How often to retry pushing queued snapshots (e.g. 90s, 15m, 1h).
+    #[arg(long, default_value = "15m", value_parser = parse_interval)]
+    interval: Duration,
+}
+
 #[derive(Args)]
 struct UninstallArgs {
     /// Skip the confirmation prompt and uninstall immediately.
     #[arg(long)]
     yes: bool,
+    /// Remove the local clone even when it holds unpushed snapshots or files linked into it
+    /// cannot be turned into copies.
+    #[arg(long)]
+    force: bool,
+}
+
+/// Selected paths, what changed, and what was skipped during `backup`.
+#[derive(Serialize)]
+struct BackupReport {
+    paths: Vec<String>,
+    bundles: Vec<String>,
+    changed_paths: Vec<String>,
+    changed_bundles: Vec<String>,
+    skipped: Vec<Skipped>,
+    #[serde(flatten)]
+    published: Published,
+    /// Whether `paths` were picked in the selector, and so should be pre-selected next time.
+    #[serde(skip)]
+    picked: bool,
+}
+
+impl BackupReport {
+    fn outcome(&self) -> Outcome {
+        if self.published.changed {
+            Outcome::Changed(self.changed_bundles.clone())
+        } else {
+            Outcome::Unchanged
+        }
+    }
+
+    fn record(&self, state: &mut SyncState) -> Result<()> {
+        state.last_backup = Some(state::now());
+        if self.published.commit.is_some() {
+            state.commit = self.published.commit.clone();
+        }
+        state.pending = self.published.queued;
+        if self.picked {
+            state.last_selection.backup = self.paths.clone();
+        }
+        state.record_paths(&self.paths)
+    }
+}
+
+/// Selected paths, what changed, and what was skipped during `restore`.
+#[derive(Serialize)]
+struct RestoreReport {
+    paths: Vec<String>,
+    bundles: Vec<String>,
+    changed_paths: Vec<String>,
+    changed_bundles: Vec<String>,
+    skipped: Vec<Skipped>,
+    /// Whether `paths` were picked in the selector, and so should be pre-selected next time.
+    #[serde(skip)]
+    picked: bool,
+}
+
+impl RestoreReport {
+    fn outcome(&self) -> Outcome {
+        if self.changed_paths.is_empty() {
+            Outcome::Unchanged
+        } else {
+            Outcome::Changed(self.changed_bundles.clone())
+        }
+    }
+
+    fn record(&self, state: &mut SyncState) -> Result<()> {
+        state.last_restore = Some(state::now());
+        if self.picked {
+            state.last_selection.restore = self.paths.clone();
+        }
+        state.record_paths(&self.paths)
+    }
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    git::set_verbose(cli.verbose);
+    output::set_format(cli.output);
+    if let Some(path) = cli.config {
+        config::set_config_path(path);
+    }
+    if let Some(kind) = cli.selector {
+        config::set_selector(kind);
+    }
+    if cli.non_interactive {
+        selector::set_non_interactive();
+    }
 
-    match cli.command {
-        Commands::Backup(args) => cmd_backup(args),
-        Commands::Restore(args) => cmd_restore(args),
+    let result = match cli.command {
+        Commands::Backup(args) => {
+            let result = notify_outcome("Backup", cmd_backup(args), BackupReport::outcome);
+            record_state(&result, BackupReport::record);
+            result.and_then(to_report)
+        }
+        Commands::Restore(args) => {
+            let result = notify_outcome("Restore", cmd_restore(args), RestoreReport::outcome);
+            record_state(&result, RestoreReport::record);
+            result.and_then(to_report)
+        }
+        Commands::Export(args) => cmd_export(args),
+        Commands::Packages(command) => cmd_packages(command),
+        Commands::Push => cmd_push(),
+        Commands::Status => cmd_status(),
+        Commands::Daemon(args) => cmd_daemon(args),
+        Commands::Schedule(command) => cmd_schedule(command),
+        Commands::Waybar => cmd_waybar(),
+        Commands::Doctor => cmd_doctor(),
+        Commands::Bootstrap(args) => {
+            let result = cmd_bootstrap(args);
+            record_state(&result, RestoreReport::record);
+            result.and_then(to_report)
+        }
+        Commands::Import(args) => cmd_import(args),
         Commands::Install(args) => cmd_install(args),
         Commands::Menu => cmd_menu(),
         Commands::Config(args) => cmd_config(args),
         Commands::Uninstall(args) => cmd_uninstall(args),
+    };
+
+    match result {
+        Ok(report) if output::is_json() => output::emit(&report),
+        Ok(_) => Ok(()),
+        Err(err) => {
+            if let Some(failed) = err.downcast_ref::<FailedReport>() {
+                if output::is_json() {
+                    output::emit(&failed.report)?;
+                } else {
+                    eprintln!("{failed}");
+                }
+                std::process::exit(1);
+            }
+            if output::is_json() {
+                output::emit_error(&err);
+            } else {
+                eprintln!("Error: {err:?}");
+            }
+            // A distinct status lets automation tell "needs a human" apart from a failure.
+            if selector::find_input_required(&err).is_some() {
+                std::process::exit(selector::INPUT_REQUIRED_EXIT);
+            }
+            std::process::exit(1);
+        }
     }
 }
 
-fn cmd_backup(args: BackupArgs) -> Result<()> {
+fn to_report(report: impl Serialize) -> Result<Value> {
+    Ok(serde_json::to_value(report)?)
+}
+
+fn cmd_backup(args: BackupArgs) -> Result<BackupReport> {
     let cfg = load_config()?;
     cfg.ensure_non_empty_paths()?;
 
@@ -155,117 +485,643 @@ fn cmd_backup(args: BackupArgs) -> Result<()> {
     let mut selected_paths = if !args.paths.is_empty() {
         let normalized = normalize_paths(args.paths.clone());
         validate_paths(&resolved_paths, &normalized)?;
-        normalized
+        drop_nested_paths(normalized)
     } else {
         resolved_paths.clone()
     };
 
-    let is_tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
-    let should_prompt = args.paths.is_empty() && !args.all && !args.no_ui && is_tty;
+    let selector = selector::open(&cfg.ui);
+    let should_prompt = args.paths.is_empty() && !args.all && !args.no_ui && selector.available();
     if should_prompt {
-        let choices: Vec<Choice> = resolved_paths
-            .iter()
-            .map(|path| Choice {
-                id: path.clone(),
-                label: path.clone(),
-            })
-            .collect();
-        let selection = selector::multi_select(
-            "Backup paths (type to filter)> ",
-            "Tab toggles, Shift+Tab selects all, Enter confirms, Esc cancels",
+        let home = PathBuf::from(env::var("HOME").context("HOME environment variable not set")?);
+        let choices = path_choices(&resolved_paths, args.files.then_some(home.as_path()))?;
+        let repo_dir = storage::open(&cfg)
+            .ok()
+            .and_then(|storage| storage.local_copy());
+        let last = state::load().unwrap_or_default().last_selection;
+        selected_paths = pick_paths(
+            selector.as_ref(),
+            "Backup paths",
+            "Nothing selected. Back up all tracked paths?",
+            &resolved_paths,
             &choices,
-            &[],
+            &last.backup,
+            path_preview(repo_dir)?,
         )?;
-        if !selection.is_empty() {
-            let normalized = normalize_paths(selection);
-            validate_paths(&resolved_paths, &normalized)?;
-            selected_paths = normalized;
-        }
     }
 
-    if selected_paths.is_empty() {
-        selected_paths = resolved_paths;
+    let selected_bundles = cfg.bundles_covering(&selected_paths);
+    let hook_context = HookContext {
+        paths: &selected_paths,
+        bundles: &selected_bundles,
+    };
+    hooks::run(&cfg.hooks, HookEvent::PreBackup, &hook_context)?;
+
+    let linked = cfg.linked_paths(&selected_paths)?;
+    unlink_copied_paths(&selected_paths, &linked)?;
+    let storage = storage::open(&cfg)?;
+    let local_edits = storage.local_edits()?;
+    let workspace = storage.checkout()?;
+    let mut transfer = fs_ops::snapshot(&selected_paths, &linked, workspace.path())?;
+    // Edits made through links are already in the clone, so the snapshot cannot see them.
+    for raw in fs_ops::paths_containing(&linked, &local_edits)? {
+        if !transfer.changed.contains(&raw) {
+            transfer.changed.push(raw);
+        }
+    }
+    store_config_copy(workspace.path());
+    if cfg.packages.capture
+        && let Err(err) = packages::snapshot(&SystemRunner, workspace.path())
+    {
+        say!("Skipping package list because it could not be captured: {err:#}");
     }
-
-    let temp = tempdir().context("Failed to create temporary directory")?;
-    let repo_dir = temp.path().join("repo");
-    git::clone_repo(&cfg.repo.url, &cfg.repo.branch, &repo_dir)
-        .context("Failed to clone repository")?;
-    fs_ops::snapshot(&selected_paths, &repo_dir)?;
 
     let message = args
         .message
         .unwrap_or_else(|| "Automated backup".to_string());
-    git::commit_and_push(&repo_dir, &message, &cfg.repo.branch)?;
-    println!("Backup complete.");
-    Ok(())
+    let published = storage.publish(&workspace, &message)?;
+    say!("Backup complete.");
+
+    let changed_bundles = cfg.bundles_covering(&transfer.changed);
+    let hook_context = HookContext {
+        paths: &transfer.changed,
+        bundles: &changed_bundles,
+    };
+    hooks::run(&cfg.hooks, HookEvent::PostBackup, &hook_context)?;
+    Ok(BackupReport {
+        paths: selected_paths,
+        bundles: selected_bundles,
+        changed_paths: transfer.changed,
+        changed_bundles,
+        skipped: transfer.skipped,
+        published,
+        picked: should_prompt,
+    })
 }
 
-fn cmd_restore(args: RestoreArgs) -> Result<()> {
-    let cfg = load_config()?;
+fn cmd_restore(args: RestoreArgs) -> Result<RestoreReport> {
+    // A bundle carries its own config, so a fresh machine can restore it before running init.
+    let config_path = config::config_file_path()?;
+    let (cfg, mut fetched) = match &args.from_bundle {
+        Some(bundle) if !config_path.exists() => {
+            say!(
+                "No config at {}; using the settings stored in the bundle.",
+                config_path.display()
+            );
+            let (cfg, workspace) = bundle_config(bundle)?;
+            (cfg, Some(workspace))
+        }
+        _ => (load_config()?, None),
+    };
     cfg.ensure_non_empty_paths()?;
 
     let resolved_paths = cfg.resolved_paths()?;
     let mut selected_paths = if !args.paths.is_empty() {
         let normalized = normalize_paths(args.paths.clone());
         validate_paths(&resolved_paths, &normalized)?;
-        normalized
+        drop_nested_paths(normalized)
     } else {
         resolved_paths.clone()
     };
 
-    let is_tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
-    let should_prompt = args.paths.is_empty() && !args.all && !args.no_ui && is_tty;
+    let checkout = || match &args.from_bundle {
+        Some(bundle) => storage::open_bundle(&cfg, bundle)?.checkout(),
+        None => storage::open(&cfg)?.checkout(),
+    };
+    let selector = selector::open(&cfg.ui);
+    let should_prompt = args.paths.is_empty() && !args.all && !args.no_ui && selector.available();
+    // Listing files needs the snapshot; otherwise keep fetching after the pre-restore hook.
     if should_prompt {
-        let choices: Vec<Choice> = resolved_paths
-            .iter()
-            .map(|path| Choice {
-                id: path.clone(),
-                label: path.clone(),
-            })
-            .collect();
-        let selection = selector::multi_select(
-            "Restore paths (type to filter)> ",
-            "Tab toggles, Shift+Tab selects all, Enter confirms, Esc cancels",
+        if args.files && fetched.is_none() {
+            fetched = Some(checkout()?);
+        }
+        let root = fetched.as_ref().map(|workspace| workspace.path());
+        let choices = path_choices(&resolved_paths, root)?;
+        let repo_dir = match root {
+            Some(root) => Some(root.to_path_buf()),
+            None if args.from_bundle.is_some() => None,
+            None => storage::open(&cfg)
+                .ok()
+                .and_then(|storage| storage.local_copy()),
+        };
+        let last = state::load().unwrap_or_default().last_selection;
+        selected_paths = pick_paths(
+            selector.as_ref(),
+            "Restore paths",
+            "Nothing selected. Restore all tracked paths?",
+            &resolved_paths,
             &choices,
-            &[],
+            &last.restore,
+            path_preview(repo_dir)?,
         )?;
-        if !selection.is_empty() {
-            let normalized = normalize_paths(selection);
-            validate_paths(&resolved_paths, &normalized)?;
-            selected_paths = normalized;
-        }
     }
 
-    if selected_paths.is_empty() {
-        selected_paths = resolved_paths;
+    let selected_bundles = cfg.bundles_covering(&selected_paths);
+    let hook_context = HookContext {
+        paths: &selected_paths,
+        bundles: &selected_bundles,
+    };
+    hooks::run(&cfg.hooks, HookEvent::PreRestore, &hook_context)?;
+
+    let mut linked = cfg.linked_paths(&selected_paths)?;
+    unlink_copied_paths(&selected_paths, &linked)?;
+    if args.from_bundle.is_some() && !linked.is_empty() {
+        say!("Copying linked paths because a bundle file has no local clone to link to.");
+        linked.clear();
+    }
+    let workspace = match fetched {
+        Some(workspace) => workspace,
+        None => checkout()?,
+    };
+    let transfer = fs_ops::restore(&selected_paths, &linked, workspace.path())?;
+    say!("Restore complete.");
+
+    let changed_bundles = cfg.bundles_covering(&transfer.changed);
+    hooks::run_bundle_actions(&cfg.hooks, &changed_bundles, &transfer.changed)?;
+    let hook_context = HookContext {
+        paths: &transfer.changed,
+        bundles: &changed_bundles,
+    };
+    hooks::run(&cfg.hooks, HookEvent::PostRestore, &hook_context)?;
+
+    Ok(RestoreReport {
+        paths: selected_paths,
+        bundles: selected_bundles,
+        changed_paths: transfer.changed,
+        changed_bundles,
+        skipped: transfer.skipped,
+        picked: should_prompt,
+    })
+}
+
+/// Settings for restoring `bundle` without a local config: the config its snapshot carries,
+/// or else every path found in it. Returns the checked-out bundle along with them.
+fn bundle_config(bundle: &Path) -> Result<(SyncConfig, Workspace)> {
+    let mut cfg = SyncConfig {
+        version: config::CONFIG_VERSION,
+        repo: RepoConfig::default(),
+        files: FileConfig {
+            paths: Vec::new(),
+            bundles: Vec::new(),
+            mode: DeployMode::default(),
+            modes: BTreeMap::new(),
+        },
+        storage: StorageConfig::default(),
+        packages: PackagesConfig::default(),
+        hooks: HooksConfig::default(),
+        notifications: NotificationsConfig::default(),
+        ui: UiConfig::default(),
+    };
+    let workspace = storage::open_bundle(&cfg, bundle)?.checkout()?;
+    let stored = config::snapshot_config_path(workspace.path());
+    if stored.is_file() {
+        let raw = fs::read_to_string(&stored)
+            .with_context(|| format!("Failed to read {}", stored.display()))?;
+        cfg = config::config_from_source(&stored, &raw)?;
+    } else {
+        cfg.files.paths = fs_ops::snapshot_paths(workspace.path())?;
     }
+    config::apply_overrides(&mut cfg);
+    Ok((cfg, workspace))
+}
 
-    let temp = tempdir().context("Failed to create temporary directory")?;
-    let repo_dir = temp.path().join("repo");
-    git::clone_repo(&cfg.repo.url, &cfg.repo.branch, &repo_dir)
-        .context("Failed to clone repository")?;
-    fs_ops::restore(&selected_paths, &repo_dir)?;
-    println!("Restore complete.");
+/// Turn links into the clone back into copies for paths that are no longer in link mode, so
+/// updating the clone cannot take their edits with it.
+fn unlink_copied_paths(selected: &[String], linked: &[String]) -> Result<()> {
+    let copied: Vec<String> = selected
+        .iter()
+        .filter(|raw| !linked.contains(raw))
+        .cloned()
+        .collect();
+    let unlinked = fs_ops::unlink_tree(&copied, &storage::local_repo_dir()?)?;
+    if !unlinked.is_empty() {
+        say!(
+            "Replaced links into the local clone with copies for {} (no longer in link mode).",
+            unlinked.join(", ")
+        );
+    }
+    Ok(())
+}
 
-    match Command::new("hyprctl").arg("reload").status() {
-        Ok(status) if status.success() => println!("hyprctl reload executed."),
-        Ok(status) => println!(
-            "hyprctl reload exited with status {:?}; continuing.",
-            status.code()
+/// Store the default config file in a snapshot, reporting (but not failing on) problems.
+fn store_config_copy(repo_dir: &Path) {
+    match config::snapshot_config(repo_dir) {
+        Ok(true) => {}
+        Ok(false) => say!(
+            "Skipping config copy because there is no config at the default location (files passed with --config or $OMARCHY_SYNCD_CONFIG are not stored)."
         ),
+        Err(err) => say!("Skipping config copy because it could not be saved: {err:#}"),
+    }
+}
+
+fn cmd_bootstrap(args: BootstrapArgs) -> Result<RestoreReport> {
+    let config_path = config::config_file_path()?;
+    if config_path.exists() && !args.force {
+        anyhow::bail!(
+            "Config already exists at {}. Run 'omarchy-syncd restore' or re-run with --force to replace it.",
+            config_path.display()
+        );
+    }
+    if !args.yes {
+        selector::require_input("Confirming the fetched config (pass --yes)")?;
+        if !io::stdin().is_terminal() {
+            anyhow::bail!("Not running in a terminal; pass --yes to write the fetched config.");
+        }
+    }
+
+    say!("Fetching {} (branch {})...", args.repo_url, args.branch);
+    let remote = SyncConfig {
+        version: config::CONFIG_VERSION,
+        repo: RepoConfig {
+            url: args.repo_url.clone(),
+            branch: args.branch.clone(),
+        },
+        files: FileConfig {
+            paths: Vec::new(),
+            bundles: Vec::new(),
+            mode: DeployMode::default(),
+            modes: BTreeMap::new(),
+        },
+        storage: StorageConfig::default(),
+        packages: PackagesConfig::default(),
+        hooks: HooksConfig::default(),
+        notifications: NotificationsConfig::default(),
+        ui: UiConfig::default(),
+    };
+    let workspace = storage::open(&remote)?.checkout()?;
+    let stored = config::snapshot_config_path(workspace.path());
+    let raw = fs::read_to_string(&stored).with_context(|| {
+        format!(
+            "No config found in {}. Run 'omarchy-syncd backup' on a machine that already uses this repo first.",
+            args.repo_url
+        )
+    })?;
+
+    // Keep the URL used here: a fresh machine may only be able to reach the repo over HTTPS.
+    let mut doc = ConfigDocument::from_source(config_path.clone(), &raw)?;
+    doc.set("repo.url", &args.repo_url)?;
+    doc.set("repo.branch", &args.branch)?;
+
+    if !args.yes {
+        say!("{}", doc.contents().trim_end());
+        if !selector::prompt_yes_no(&format!("Write this config to {}?", config_path.display()))? {
+            return Err(
+                selector::Cancelled::new("Bootstrap cancelled; nothing was written.").into(),
+            );
+        }
+    }
+    if config_path.exists() {
+        let backup = config::backup_path(&config_path);
+        fs::rename(&config_path, &backup)
+            .with_context(|| format!("Failed to move {} aside", config_path.display()))?;
+        say!("Moved the previous config to {}", backup.display());
+    }
+    doc.save()?;
+    say!("Wrote config to {}", config_path.display());
+
+    cmd_restore(RestoreArgs {
+        paths: Vec::new(),
+        all: args.all,
+        no_ui: false,
+        from_bundle: None,
+        files: false,
+    })
+}
+
+fn cmd_import(args: ImportArgs) -> Result<Value> {
+    say!(
+        "Reading {} dotfiles from {}...",
+        args.from.as_str(),
+        args.source
+    );
+    let imported = import::load(args.from, &args.source)?;
+    let tracked = imported.tracked_paths();
+    let bundles = detect_bundles_from_paths(&tracked);
+    let paths = prune_explicit_paths(&bundles, tracked)?;
+    print_selection(&bundles, &paths);
+
+    let files: Vec<String> = imported
+        .files
+        .iter()
+        .map(|rel| format!("~/{}", rel.display()))
+        .collect();
+    if args.dry_run {
+        return Ok(json!({
+            "bundles": bundles,
+            "paths": paths,
+            "files": files,
+            "skipped": imported.skipped,
+            "dry_run": true,
+        }));
+    }
+
+    let mut doc = ConfigDocument::open().context(
+        "Missing config. Run 'omarchy-syncd config --write --repo-url <remote> ...' first.",
+    )?;
+    for id in &bundles {
+        doc.add_to_list("bundles", id)?;
+    }
+    for path in &paths {
+        doc.add_to_list("paths", path)?;
+    }
+    doc.save()?;
+    let config_path = config::config_file_path()?;
+    say!("Saved selection to {}", config_path.display());
+
+    let cfg = load_config()?;
+    let storage = storage::open(&cfg)?;
+    let workspace = storage.checkout()?;
+    imported.write_snapshot(workspace.path())?;
+    store_config_copy(workspace.path());
+    let message = format!("Import from {}", args.from.as_str());
+    let published = storage.publish(&workspace, &message)?;
+    say!(
+        "Imported {} file(s). Run 'omarchy-syncd restore' to apply them to this machine.",
+        files.len()
+    );
+
+    Ok(json!({
+        "bundles": bundles,
+        "paths": paths,
+        "files": files,
+        "skipped": imported.skipped,
+        "dry_run": false,
+        "config": config_path,
+        "published": published,
+    }))
+}
+
+/// Send the configured desktop notification for a finished backup or restore.
+fn notify_outcome<T>(
+    operation: &str,
+    result: Result<T>,
+    outcome: impl FnOnce(&T) -> Outcome,
+) -> Result<T> {
+    let notifications = load_config()
+        .map(|cfg| cfg.notifications)
+        .unwrap_or_default();
+    let outcome = match &result {
+        Ok(report) => outcome(report),
+        Err(err) if selector::is_cancelled(err) => return result,
+        Err(err) => Outcome::Failed(format!("{err:#}")),
+    };
+    if let Err(err) = notify::report(&notifications, &NotifySend, operation, &outcome) {
+        eprintln!("Failed to send notification: {err:#}");
+    }
+    result
+}
+
+/// Cache the result of a backup or restore for `waybar`; failing to do so only warns.
+fn record_state<T>(result: &Result<T>, record: impl FnOnce(&T, &mut SyncState) -> Result<()>) {
+    if let Err(err) = result
+        && selector::is_cancelled(err)
+    {
+        return;
+    }
+    let recorded = state::update(|state| match result {
+        Ok(report) => {
+            state.last_error = None;
+            record(report, state)
+        }
         Err(err) => {
-            if err.kind() != std::io::ErrorKind::NotFound {
-                println!("hyprctl reload failed: {}", err);
+            state.last_error = Some(format!("{err:#}"));
+            Ok(())
+        }
+    });
+    if let Err(err) = recorded {
+        say!("Could not update the cached sync state: {err:#}");
+    }
+}
+
+fn cmd_export(args: ExportArgs) -> Result<Value> {
+    let cfg = load_config()?;
+    let storage = storage::open(&cfg)?;
+    let out = env::current_dir()
+        .context("Could not determine the current directory")?
+        .join(&args.out);
+
+    let workspace = storage.checkout()?;
+    let latest_only = args.latest || cfg.storage.kind != StorageKind::Git;
+    if latest_only {
+        if !args.latest {
+            say!(
+                "{} storage keeps no history; exporting the latest snapshot only.",
+                cfg.storage.kind.as_str()
+            );
+        }
+        git::bundle_snapshot(workspace.path(), &cfg.repo.branch, &out)?;
+    } else {
+        git::bundle_history(workspace.path(), &cfg.repo.branch, &out)?;
+    }
+
+    say!("Exported snapshot bundle to {}", out.display());
+    Ok(json!({ "out": out, "history": !latest_only }))
+}
+
+fn cmd_packages(command: PackagesCommand) -> Result<Value> {
+    let cfg = load_config()?;
+    let storage = storage::open(&cfg)?;
+    let workspace = storage.checkout()?;
+    let manifest = packages::load_manifest(workspace.path())?;
+    let runner = SystemRunner;
+    let diff = packages::diff(&runner, &manifest)?;
+
+    let report = json!({
+        "missing_native": diff.missing_native,
+        "missing_foreign": diff.missing_foreign,
+    });
+    if diff.is_empty() {
+        say!("All recorded packages are installed.");
+        return Ok(report);
+    }
+
+    match command {
+        PackagesCommand::Diff => {
+            print_package_list("Missing packages", &diff.missing_native);
+            print_package_list("Missing AUR packages", &diff.missing_foreign);
+        }
+        PackagesCommand::Install => {
+            packages::install(&runner, &diff, cfg.packages.aur_helper.as_deref())?;
+            say!("Package installation complete.");
+        }
+    }
+    Ok(report)
+}
+
+fn cmd_push() -> Result<Value> {
+    let cfg = load_config()?;
+    let storage = storage::open(&cfg)?;
+    let pushed = storage.flush()?;
+    if pushed == 0 {
+        say!("Nothing to push.");
+    } else {
+        say!("Pushed {pushed} queued snapshot(s).");
+        clear_pending()?;
+    }
+    Ok(json!({ "pushed": pushed }))
+}
+
+fn clear_pending() -> Result<()> {
+    state::update(|state| {
+        state.pending = 0;
+        Ok(())
+    })
+}
+
+fn cmd_waybar() -> Result<Value> {
+    let module = match waybar_module() {
+        Ok(module) => module,
+        Err(err) => waybar::Module {
+            text: "!".to_string(),
+            tooltip: format!("{err:#}"),
+            class: "error",
+        },
+    };
+    if !output::is_json() {
+        println!("{}", serde_json::to_string(&module)?);
+    }
+    to_report(module)
+}
+
+fn waybar_module() -> Result<waybar::Module> {
+    let cfg = load_config()?;
+    let state = state::load()?;
+    let modified = if state.last_backup.is_some() || state.last_restore.is_some() {
+        state.modified_paths_throttled(&cfg.resolved_paths()?)?
+    } else {
+        Vec::new()
+    };
+    Ok(waybar::render(&state, &modified, state::now()))
+}
+
+fn cmd_doctor() -> Result<Value> {
+    let checks = doctor::run(&SystemRunner);
+    for check in &checks {
+        say!(
+            "[{}] {}: {}",
+            check.status.as_str(),
+            check.name,
+            check.detail
+        );
+    }
+    let failures = doctor::count(&checks, Status::Fail);
+    let warnings = doctor::count(&checks, Status::Warn);
+    let report = json!({
+        "ok": failures == 0,
+        "checks": checks,
+        "failures": failures,
+        "warnings": warnings,
+    });
+    if failures > 0 {
+        return Err(FailedReport::new(report, format!("{failures} check(s) failed.")).into());
+    }
+    say!("All checks passed ({warnings} warning(s)).");
+    Ok(report)
+}
+
+fn cmd_status() -> Result<Value> {
+    let cfg = load_config()?;
+    let storage = storage::open(&cfg)?;
+    let mut report = json!({ "storage": cfg.storage.kind.as_str() });
+    match cfg.storage.kind {
+        StorageKind::Git => {
+            say!(
+                "Storage: git ({}, branch {})",
+                cfg.repo.url,
+                cfg.repo.branch
+            );
+            let local = storage::local_repo_dir()?;
+            let has_local = local.join(".git").is_dir();
+            if has_local {
+                say!("Local copy: {}", local.display());
+            } else {
+                say!("Local copy: (not created yet)");
             }
+            report["url"] = json!(cfg.repo.url);
+            report["branch"] = json!(cfg.repo.branch);
+            report["local_copy"] = json!(has_local.then_some(local));
+        }
+        kind => {
+            say!(
+                "Storage: {} ({})",
+                kind.as_str(),
+                cfg.storage.path.as_deref().unwrap_or("(no path set)")
+            );
+            report["path"] = json!(cfg.storage.path);
         }
     }
+    let pending = storage.pending()?;
+    say!("Pending snapshots: {pending}");
+    report["pending"] = json!(pending);
+    Ok(report)
+}
 
-    Ok(())
+/// Runs until killed; under `--output json` each push prints its own JSON document.
+fn cmd_daemon(args: DaemonArgs) -> Result<Value> {
+    let cfg = load_config()?;
+    let storage = storage::open(&cfg)?;
+    loop {
+        match storage.flush() {
+            Ok(0) => {}
+            Ok(pushed) => {
+                say!("Pushed {pushed} queued snapshot(s).");
+                clear_pending()?;
+                if output::is_json() {
+                    output::emit(&json!({ "pushed": pushed }))?;
+                }
+            }
+            Err(err) if git::is_unreachable(&err) => {}
+            Err(err) if output::is_json() => output::emit_error(&err),
+            Err(err) => eprintln!("Push failed: {err:#}"),
+        }
+        thread::sleep(args.interval);
+    }
+}
+
+fn cmd_schedule(command: ScheduleCommand) -> Result<Value> {
+    let runner = SystemRunner;
+    match command {
+        ScheduleCommand::Enable(args) => {
+            let exe = env::current_exe().context("Could not determine current executable path")?;
+            schedule::enable(&runner, &exe, args.every)?;
+            let every = schedule::format_interval(args.every);
+            say!(
+                "Scheduled backups every {every} via {}.",
+                schedule::TIMER_NAME
+            );
+            Ok(json!({ "enabled": true, "every": every }))
+        }
+        ScheduleCommand::Status => {
+            let status = schedule::status(&runner)?;
+            match &status.every {
+                Some(every) => {
+                    say!("Scheduled backups every {every}.");
+                    if let Some(active) = &status.active {
+                        say!("Timer: {active}");
+                    }
+                }
+                None => say!("Scheduled backups are not enabled."),
+            }
+            Ok(json!({
+                "enabled": status.every.is_some(),
+                "every": status.every,
+                "active": status.active,
+            }))
+        }
+        ScheduleCommand::Disable => {
+            let removed = schedule::disable(&runner)?;
+            if removed {
+                say!("Scheduled backups disabled.");
+            } else {
+                say!("Scheduled backups are not enabled.");
+            }
+            Ok(json!({ "enabled": false, "removed": removed }))
+        }
+    }
 }
 
-fn cmd_install(args: InstallArgs) -> Result<()> {
-    let mut cfg = load_config().context(
+fn cmd_install(args: InstallArgs) -> Result<Value> {
+    let cfg = load_config().context(
         "Missing config. Run 'omarchy-syncd config --write --repo-url <remote> ...' first.",
     )?;
 
@@ -283,11 +1139,9 @@ fn cmd_install(args: InstallArgs) -> Result<()> {
         cfg.files.paths.clone()
     };
 
-    let should_prompt = args.bundles.is_empty()
-        && args.paths.is_empty()
-        && !args.no_ui
-        && std::io::stdin().is_terminal()
-        && std::io::stdout().is_terminal();
+    let selector = ui_selector();
+    let should_prompt =
+        args.bundles.is_empty() && args.paths.is_empty() && !args.no_ui && selector.available();
 
     selected_bundles = normalize_bundles(selected_bundles);
     bundles::ensure_known(&selected_bundles)?;
@@ -295,7 +1149,7 @@ fn cmd_install(args: InstallArgs) -> Result<()> {
     let base_paths = prune_explicit_paths(&selected_bundles, explicit_paths.clone())?;
 
     if should_prompt {
-        let selection = interactive_selection(&selected_bundles, &base_paths)?;
+        let selection = interactive_selection(selector.as_ref(), &selected_bundles, &base_paths)?;
         selected_bundles = selection.bundles;
         explicit_paths = selection.paths;
     } else {
@@ -313,22 +1167,35 @@ fn cmd_install(args: InstallArgs) -> Result<()> {
 
     if args.dry_run {
         print_selection(&selected_bundles, &explicit_paths);
-        return Ok(());
+        return Ok(json!({
+            "bundles": selected_bundles,
+            "paths": explicit_paths,
+            "dry_run": true,
+        }));
     }
 
-    cfg.files.bundles = selected_bundles;
-    cfg.files.paths = explicit_paths;
-    write_config(&cfg)?;
+    // Edit the file in place: rewriting it from `cfg` would drop its comments and persist
+    // any `OMARCHY_SYNCD_*` overrides.
+    let mut doc = ConfigDocument::open()?;
+    doc.replace_list("bundles", &selected_bundles)?;
+    doc.replace_list("paths", &explicit_paths)?;
+    doc.save()?;
 
-    print_selection(&cfg.files.bundles, &cfg.files.paths);
-    println!(
-        "Saved selection to {}",
-        config::config_file_path()?.display()
-    );
-    Ok(())
+    print_selection(&selected_bundles, &explicit_paths);
+    let config_path = config::config_file_path()?;
+    say!("Saved selection to {}", config_path.display());
+    Ok(json!({
+        "bundles": selected_bundles,
+        "paths": explicit_paths,
+        "dry_run": false,
+        "config": config_path,
+    }))
 }
 
-fn cmd_menu() -> Result<()> {
+fn cmd_menu() -> Result<Value> {
+    if output::is_json() {
+        anyhow::bail!("The menu is interactive and has no JSON output; run a subcommand instead.");
+    }
     let header = "Enter runs selection • Esc cancels";
     let menu_entries = [
         ("install", "Install", "Configure tracked bundles or paths"),
@@ -362,24 +1229,46 @@ fn cmd_menu() -> Result<()> {
         })
         .collect();
 
-    let selection =
-        selector::single_select("Omarchy Syncd (type to filter) >", header, &choices, &[])?;
+    let selection = ui_selector().single_select(&Menu::new("Omarchy Syncd", header, &choices))?;
     match selection.as_str() {
-        "install" => run_subcommand(&["install"]),
-        "backup" => run_subcommand(&["backup"]),
-        "restore" => run_subcommand(&["restore"]),
-        "config" => run_subcommand(&["config"]),
-        "uninstall" => run_subcommand(&["uninstall"]),
+        "install" => run_subcommand(&["install"])?,
+        "backup" => run_subcommand(&["backup"])?,
+        "restore" => run_subcommand(&["restore"])?,
+        "config" => run_subcommand(&["config"])?,
+        "uninstall" => run_subcommand(&["uninstall"])?,
         other => anyhow::bail!("Unknown selection {other}"),
     }
+    Ok(json!({ "selection": selection }))
 }
 
-fn cmd_config(args: ConfigArgs) -> Result<()> {
+fn cmd_config(args: ConfigArgs) -> Result<Value> {
+    if let Some(command) = args.command {
+        return match command {
+            ConfigCommand::Validate => cmd_config_validate(),
+            ConfigCommand::Migrate(args) => cmd_config_migrate(args),
+            ConfigCommand::Show { effective } => cmd_config_show(effective),
+            ConfigCommand::Get { key } => cmd_config_get(&key),
+            ConfigCommand::Set { key, value } => cmd_config_set(&key, &value),
+            ConfigCommand::AddPath { paths } => cmd_config_list("paths", &paths, true),
+            ConfigCommand::RemovePath { paths } => cmd_config_list("paths", &paths, false),
+            ConfigCommand::AddBundle { bundles } => cmd_config_list("bundles", &bundles, true),
+            ConfigCommand::RemoveBundle { bundles } => cmd_config_list("bundles", &bundles, false),
+        };
+    }
     if args.write {
-        let repo_url = args
-            .repo_url
-            .clone()
-            .ok_or_else(|| anyhow::anyhow!("--repo-url is required when using --write"))?;
+        let repo_url = match (&args.repo_url, args.storage) {
+            (Some(url), _) => url.clone(),
+            (None, StorageKind::Git) => {
+                anyhow::bail!("--repo-url is required when using --write")
+            }
+            (None, _) => String::new(),
+        };
+        if args.storage != StorageKind::Git && args.storage_path.is_none() {
+            anyhow::bail!(
+                "--storage-path is required when using --storage {}",
+                args.storage.as_str()
+            );
+        }
         let mut bundles = args.bundles.clone();
         if args.include_defaults {
             bundles.extend(bundles::DEFAULT_BUNDLE_IDS.iter().map(|id| id.to_string()));
@@ -387,31 +1276,36 @@ fn cmd_config(args: ConfigArgs) -> Result<()> {
         let write_opts = ConfigWriteOptions {
             repo_url,
             branch: args.branch.clone(),
+            storage: StorageConfig {
+                kind: args.storage,
+                path: args.storage_path.clone(),
+                ..StorageConfig::default()
+            },
             bundles,
             paths: args.paths.clone(),
             verify_remote: args.verify_remote,
             force: args.force,
         };
         let written_path = write_sync_configuration(write_opts)?;
-        println!("Wrote config to {}", written_path.display());
-        return Ok(());
+        say!("Wrote config to {}", written_path.display());
+        return Ok(json!({ "path": written_path, "written": true }));
     }
 
     let config_path = config::config_file_path()?;
 
     if args.print_path {
-        println!("{}", config_path.display());
-        return Ok(());
+        say!("{}", config_path.display());
+        return Ok(json!({ "path": config_path }));
     }
 
     if args.create {
         let created = ensure_config_file(&config_path)?;
         if created {
-            println!("Created config at {}", config_path.display());
+            say!("Created config at {}", config_path.display());
         } else {
-            println!("Config already exists at {}", config_path.display());
+            say!("Config already exists at {}", config_path.display());
         }
-        return Ok(());
+        return Ok(json!({ "path": config_path, "created": created }));
     }
 
     if !config_path.exists() {
@@ -437,37 +1331,215 @@ fn cmd_config(args: ConfigArgs) -> Result<()> {
         },
     ];
 
-    let selection = selector::single_select(
-        "Config actions (type to filter) >",
-        header,
-        &choices,
-        &["ctrl-o:accept"],
-    )?;
+    let menu = Menu::new("Config actions", header, &choices).binds(&["ctrl-o:accept"]);
+    let selection = ui_selector().single_select(&menu)?;
 
     match selection.as_str() {
         "open_editor" => open_config_in_editor(args.editor.clone())?,
-        "print_path" => println!("{}", config_path.display()),
+        "print_path" => say!("{}", config_path.display()),
         "ensure_exists" => {
             let created = ensure_config_file(&config_path)?;
             if created {
-                println!("Created config at {}", config_path.display());
+                say!("Created config at {}", config_path.display());
             } else {
-                println!("Config already exists at {}", config_path.display());
+                say!("Config already exists at {}", config_path.display());
             }
         }
         other => anyhow::bail!("Unknown selection {other}"),
     }
 
-    Ok(())
+    Ok(json!({ "path": config_path }))
+}
+
+fn cmd_config_validate() -> Result<Value> {
+    let (path, raw) = config::read_config_source()?;
+    let problems = validate::check(&raw);
+    for problem in &problems {
+        say!(
+            "{}:{}:{}: {}: {}",
+            path.display(),
+            problem.line,
+            problem.column,
+            problem.severity.as_str(),
+            problem.message
+        );
+    }
+    let valid = !validate::has_errors(&problems);
+    let report = json!({ "ok": valid, "path": path, "problems": problems });
+    if !valid {
+        let summary = format!("{} has errors.", path.display());
+        return Err(FailedReport::new(report, summary).into());
+    }
+    say!("{} is valid.", path.display());
+    Ok(report)
 }
 
-fn cmd_uninstall(args: UninstallArgs) -> Result<()> {
+fn cmd_config_migrate(args: MigrateArgs) -> Result<Value> {
+    let (path, raw) = config::read_config_source()?;
+    let Some(migration) = config::migrate_source(&raw)? else {
+        say!(
+            "{} is already at version {}.",
+            path.display(),
+            config::CONFIG_VERSION
+        );
+        return Ok(json!({ "path": path, "migrated": false, "version": config::CONFIG_VERSION }));
+    };
+
+    let diff = similar::TextDiff::from_lines(&migration.before, &migration.after)
+        .unified_diff()
+        .header(
+            &format!("{} (version {})", path.display(), migration.from),
+            &format!("{} (version {})", path.display(), migration.to),
+        )
+        .to_string();
+    let report = json!({
+        "path": path,
+        "from": migration.from,
+        "to": migration.to,
+        "migrated": !args.dry_run,
+        "diff": diff,
+    });
+    if args.dry_run {
+        say!("{}", diff.trim_end());
+        return Ok(report);
+    }
+
+    config::write_migration(&path, &migration)?;
+    say!(
+        "Migrated {} from version {} to {}; the previous file is at {}.",
+        path.display(),
+        migration.from,
+        migration.to,
+        config::backup_path(&path).display()
+    );
+    Ok(report)
+}
+
+fn cmd_config_show(effective: bool) -> Result<Value> {
+    let overrides = config::overrides();
+    if !effective {
+        let (path, raw) = config::read_config_source()?;
+        say!("{}", raw.trim_end());
+        return Ok(json!({ "path": path, "contents": raw, "overrides": overrides }));
+    }
+
+    let cfg = load_config()?;
+    let effective = cfg.effective();
+    say!("# {}", config::config_file_path()?.display());
+    for item in &overrides {
+        say!("# {} = {} (from {})", item.key, item.value, item.source);
+    }
+    say!("{}", toml::to_string_pretty(&effective)?.trim_end());
+    Ok(json!({
+        "path": config::config_file_path()?,
+        "config": effective,
+        "overrides": overrides,
+    }))
+}
+
+fn cmd_config_get(key: &str) -> Result<Value> {
+    let value = ConfigDocument::open()?.get(key)?;
+    match &value {
+        toml::Value::String(text) => say!("{text}"),
+        toml::Value::Array(items) => {
+            for item in items {
+                match item {
+                    toml::Value::String(text) => say!("{text}"),
+                    other => say!("{other}"),
+                }
+            }
+        }
+        other => say!("{other}"),
+    }
+    Ok(json!({ "key": key, "value": value }))
+}
+
+fn cmd_config_set(key: &str, value: &str) -> Result<Value> {
+    let mut doc = ConfigDocument::open()?;
+    let changed = doc.set(key, value)?;
+    if changed {
+        doc.save()?;
+        say!("Set {key} = {value}.");
+    } else {
+        say!("{key} is already {value}.");
+    }
+    Ok(json!({ "key": key, "value": doc.get(key)?, "changed": changed }))
+}
+
+/// Add or remove entries of `files.<field>`; entries already in the wanted state are skipped.
+fn cmd_config_list(field: &str, entries: &[String], add: bool) -> Result<Value> {
+    let mut doc = ConfigDocument::open()?;
+    let mut changed = Vec::new();
+    for entry in normalize_paths(entries.to_vec()) {
+        let entry = if field == "paths" {
+            fs_ops::tilde_path(&entry)
+        } else {
+            entry
+        };
+        let applied = if add {
+            doc.add_to_list(field, &entry)?
+        } else {
+            doc.remove_from_list(field, &entry)?
+        };
+        match (applied, add) {
+            (true, true) => say!("Added {entry} to files.{field}."),
+            (true, false) => say!("Removed {entry} from files.{field}."),
+            (false, true) => say!("{entry} is already in files.{field}."),
+            (false, false) => say!("{entry} is not in files.{field}."),
+        }
+        if applied {
+            changed.push(entry);
+        }
+    }
+    if !changed.is_empty() {
+        doc.save()?;
+    }
+    Ok(json!({ "key": format!("files.{field}"), "changed": changed }))
+}
+
+fn cmd_uninstall(args: UninstallArgs) -> Result<Value> {
+    // Linked files point into the clone, which is about to be deleted.
+    let repo_dir = storage::local_repo_dir()?;
+    let cfg = load_config();
+    if let Ok(cfg) = &cfg
+        && !args.force
+    {
+        let pending = storage::open(cfg)
+            .and_then(|storage| storage.pending())
+            .context("Could not check for unpushed snapshots; pass --force to uninstall anyway")?;
+        if pending > 0 {
+            anyhow::bail!(
+                "{pending} snapshot(s) have not been pushed yet and would be lost. Run `omarchy-syncd push` first, or pass --force to discard them."
+            );
+        }
+    }
+    let tracked = match cfg.and_then(|cfg| cfg.resolved_paths()) {
+        Ok(paths) => Some(paths),
+        Err(_) if args.force || !repo_dir.is_dir() => None,
+        Err(err) => {
+            return Err(err.context(
+                "Cannot tell which files link into the local clone; fix the config or pass --force to remove it anyway",
+            ));
+        }
+    };
+
     if !args.yes {
-        let proceed =
-            prompt_yes_no("This will remove omarchy-syncd completely. Do you wish to continue?")?;
+        let proceed = selector::prompt_yes_no(
+            "This will remove omarchy-syncd completely. Do you wish to continue?",
+        )?;
         if !proceed {
-            println!("Uninstall cancelled.");
-            return Ok(());
+            say!("Uninstall cancelled.");
+            return Ok(json!({ "uninstalled": false }));
+        }
+    }
+
+    if let Some(paths) = tracked {
+        let unlinked = fs_ops::unlink_tree(&paths, &repo_dir)?;
+        if !unlinked.is_empty() {
+            say!(
+                "Replaced links into the local clone with copies for {}.",
+                unlinked.join(", ")
+            );
         }
     }
 
@@ -494,8 +1566,12 @@ fn cmd_uninstall(args: UninstallArgs) -> Result<()> {
     // Remove the primary binary last
     remove_file_if_exists(&bin_dir.join("omarchy-syncd"))?;
 
+    if let Err(err) = schedule::disable(&SystemRunner) {
+        say!("Failed removing the scheduled backup timer: {err:#}");
+    }
+
     // Remove configuration directory
-    let config_path = config::config_file_path()?;
+    let config_path = config::default_config_file_path()?;
     if config_path.exists() {
         fs::remove_file(&config_path)
             .with_context(|| format!("Failed removing {}", config_path.display()))?;
@@ -505,17 +1581,23 @@ fn cmd_uninstall(args: UninstallArgs) -> Result<()> {
         fs::remove_dir_all(&config_dir)
             .with_context(|| format!("Failed removing {}", config_dir.display()))?;
     }
+    let data_dir = config::data_dir()?;
+    if data_dir.exists() {
+        fs::remove_dir_all(&data_dir)
+            .with_context(|| format!("Failed removing {}", data_dir.display()))?;
+    }
 
     remove_elephant_menu()?;
     remove_elephant_icon()?;
 
-    println!("omarchy-syncd has been uninstalled.");
-    Ok(())
+    say!("omarchy-syncd has been uninstalled.");
+    Ok(json!({ "uninstalled": true }))
 }
 
 struct ConfigWriteOptions {
     repo_url: String,
     branch: String,
+    storage: StorageConfig,
     bundles: Vec<String>,
     paths: Vec<String>,
     verify_remote: bool,
@@ -555,11 +1637,12 @@ fn write_sync_configuration(opts: ConfigWriteOptions) -> Result<std::path::PathB
         );
     }
 
-    if opts.verify_remote {
+    if opts.verify_remote && opts.storage.kind == StorageKind::Git {
         git::verify_remote(&opts.repo_url, &opts.branch).context("Remote verification failed")?;
     }
 
     let cfg = SyncConfig {
+        version: config::CONFIG_VERSION,
         repo: RepoConfig {
             url: opts.repo_url,
             branch: opts.branch,
@@ -567,7 +1650,14 @@ fn write_sync_configuration(opts: ConfigWriteOptions) -> Result<std::path::PathB
         files: FileConfig {
             paths: explicit_paths,
             bundles: bundle_vec,
+            mode: DeployMode::default(),
+            modes: BTreeMap::new(),
         },
+        storage: opts.storage,
+        packages: PackagesConfig::default(),
+        hooks: HooksConfig::default(),
+        notifications: NotificationsConfig::default(),
+        ui: UiConfig::default(),
     };
 
     write_config(&cfg)?;
@@ -581,11 +1671,18 @@ fn ensure_config_file(path: &Path) -> Result<bool> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::write(path, b"# omarchy-syncd configuration\n")?;
+    fs::write(
+        path,
+        format!(
+            "# omarchy-syncd configuration\nversion = {}\n",
+            config::CONFIG_VERSION
+        ),
+    )?;
     Ok(true)
 }
 
 fn open_config_in_editor(preferred_editor: Option<String>) -> Result<()> {
+    selector::require_input("Editing the config")?;
     let config_path = config::config_file_path()?;
     ensure_config_file(&config_path)?;
 
@@ -612,6 +1709,7 @@ struct SelectionResult {
 }
 
 fn interactive_selection(
+    selector: &dyn Selector,
     _current_bundles: &[String],
     current_paths: &[String],
 ) -> Result<SelectionResult> {
@@ -633,12 +1731,8 @@ fn interactive_selection(
             ),
         })
         .collect();
-    let mut bundle_selection = selector::multi_select(
-        "Bundle choices (type to filter)> ",
-        header,
-        &bundle_choices,
-        &[],
-    )?;
+    let mut bundle_selection =
+        selector.multi_select(&Menu::new("Bundle choices", header, &bundle_choices))?;
 
     let mut path_pool: Vec<String> = bundles::all()
         .iter()
@@ -660,15 +1754,11 @@ fn interactive_selection(
             label: path.clone(),
         })
         .collect();
-    let mut path_selection = selector::multi_select(
-        "Individual paths (type to filter)> ",
-        header,
-        &path_choices,
-        &[],
-    )?;
+    let mut path_selection =
+        selector.multi_select(&Menu::new("Individual paths", header, &path_choices))?;
 
-    while prompt_yes_no("Add another custom path?")? {
-        let custom = prompt_string("Dotfile path (e.g. ~/.config/example)")?;
+    while selector.confirm("Add another custom path?")? {
+        let custom = selector::prompt_string("Dotfile path (e.g. ~/.config/example)")?;
         if !custom.is_empty() && !path_selection.contains(&custom) {
             path_selection.push(custom);
         }
@@ -711,8 +1801,16 @@ fn normalize_paths(paths: Vec<String>) -> Vec<String> {
 
 fn run_subcommand(args: &[&str]) -> Result<()> {
     let exe = env::current_exe().context("Failed to locate omarchy-syncd executable")?;
-    let status = Command::new(exe)
-        .args(args)
+    let mut command = Command::new(exe);
+    command.args(args);
+    // Keep `--config` and `--selector` for the commands launched from the menu.
+    if let Ok(path) = config::config_file_path() {
+        command.env(config::CONFIG_ENV, path);
+    }
+    if let Some(kind) = config::selector_flag() {
+        command.env(config::SELECTOR_ENV, kind.as_str());
+    }
+    let status = command
         .status()
         .with_context(|| format!("Failed to execute subcommand {:?}", args))?;
     if status.success() {
@@ -736,33 +1834,36 @@ fn find_default_editor() -> Option<String> {
     None
 }
 
-fn prompt_yes_no(question: &str) -> Result<bool> {
-    loop {
-        print!("{question} [Y/n]: ");
-        io::stdout().flush()?;
-        let mut line = String::new();
-        io::stdin().read_line(&mut line)?;
-        let trimmed = line.trim();
-        match trimmed.to_lowercase().as_str() {
-            "y" | "yes" => return Ok(true),
-            "n" | "no" => return Ok(false),
-            _ => println!("Please answer 'y' or 'n'."),
-        }
+fn parse_interval(raw: &str) -> Result<Duration, String> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (digits, unit) = raw.split_at(split);
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid interval '{raw}'; expected e.g. 90s, 15m, or 1h"))?;
+    let seconds = match unit {
+        "" | "s" => value,
+        "m" => value * 60,
+        "h" => value * 60 * 60,
+        "d" => value * 60 * 60 * 24,
+        _ => return Err(format!("unknown interval unit '{unit}'; use s, m, h, or d")),
+    };
+    if seconds == 0 {
+        return Err("interval must be greater than zero".to_string());
     }
+    Ok(Duration::from_secs(seconds))
 }
 
-fn prompt_string(prompt: &str) -> Result<String> {
-    print!("{prompt}: ");
-    io::stdout().flush()?;
-    let mut line = String::new();
-    io::stdin().read_line(&mut line)?;
-    Ok(line.trim().to_string())
-}
-
+/// Accept configured paths and anything inside them, such as one file of a tracked directory.
 fn validate_paths(valid: &[String], selected: &[String]) -> Result<()> {
-    let known: HashSet<&str> = valid.iter().map(|p| p.as_str()).collect();
     for path in selected {
-        if !known.contains(path.as_str()) {
+        let inside = Path::new(path)
+            .components()
+            .all(|component| component != std::path::Component::ParentDir)
+            && valid
+                .iter()
+                .any(|configured| Path::new(path).starts_with(configured));
+        if !inside {
             anyhow::bail!(
                 "Path {} is not part of the configured backup set. Use 'omarchy-syncd install' to add it first.",
                 path
@@ -772,12 +1873,94 @@ fn validate_paths(valid: &[String], selected: &[String]) -> Result<()> {
     Ok(())
 }
 
+/// Drop selected paths that sit inside another selected path.
+fn drop_nested_paths(paths: Vec<String>) -> Vec<String> {
+    paths
+        .iter()
+        .filter(|path| {
+            !paths
+                .iter()
+                .any(|other| other != *path && Path::new(path).starts_with(other))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Selector entries for `paths`; with `root`, each tracked directory is followed by the
+/// files it holds there so single files can be picked.
+fn path_choices(paths: &[String], root: Option<&Path>) -> Result<Vec<Choice>> {
+    let mut choices = Vec::new();
+    for path in paths {
+        choices.push(Choice {
+            id: path.clone(),
+            label: path.clone(),
+        });
+        if let Some(root) = root {
+            for file in fs_ops::files_under(path, root)? {
+                choices.push(Choice {
+                    id: file.clone(),
+                    label: file,
+                });
+            }
+        }
+    }
+    Ok(choices)
+}
+
+/// The configured selector, for commands that can run before a valid config exists.
+fn ui_selector() -> Box<dyn Selector> {
+    let ui = load_config().map(|cfg| cfg.ui).unwrap_or_else(|_| {
+        let mut ui = UiConfig::default();
+        config::apply_ui_overrides(&mut ui);
+        ui
+    });
+    selector::open(&ui)
+}
+
+/// Let the user pick among `choices`, starting from the paths picked last time. Confirming an
+/// empty pick with `confirm_all` selects every tracked path; declining cancels.
+fn pick_paths(
+    selector: &dyn Selector,
+    title: &str,
+    confirm_all: &str,
+    resolved_paths: &[String],
+    choices: &[Choice],
+    preselected: &[String],
+    preview: selector::Preview,
+) -> Result<Vec<String>> {
+    let menu = Menu::new(
+        title,
+        "Tab toggles, Shift+Tab selects all, Enter confirms, Esc cancels",
+        choices,
+    )
+    .preselect(preselected)
+    .preview(preview);
+    let selection = selector.multi_select(&menu)?;
+    if !selection.is_empty() {
+        let normalized = normalize_paths(selection);
+        validate_paths(resolved_paths, &normalized)?;
+        return Ok(drop_nested_paths(normalized));
+    }
+    // skim returns the highlighted entry when none is toggled, so there this means the filter
+    // hid everything; the other selectors can confirm an empty list on purpose.
+    if !selector.confirm(confirm_all)? {
+        return Err(selector::Cancelled::new("Selection cancelled: no paths selected").into());
+    }
+    Ok(resolved_paths.to_vec())
+}
+
+/// Preview pane for path selectors, comparing against the snapshot in `repo_dir` when known.
+fn path_preview(repo_dir: Option<PathBuf>) -> Result<selector::Preview> {
+    let previewer = Previewer::new(repo_dir)?;
+    Ok(Arc::new(move |raw| previewer.render(raw)))
+}
+
 fn prune_explicit_paths(bundles: &[String], paths: Vec<String>) -> Result<Vec<String>> {
     if bundles.is_empty() {
         return Ok(paths);
     }
 
-    let bundle_ids: Vec<String> = bundles.iter().cloned().collect();
+    let bundle_ids: Vec<String> = bundles.to_vec();
     let bundle_paths: HashSet<String> = bundles::resolve_paths(&bundle_ids)?.into_iter().collect();
     Ok(paths
         .into_iter()
@@ -796,24 +1979,35 @@ fn remove_file_if_exists(path: &Path) -> Result<()> {
 
 fn print_selection(bundles: &[String], paths: &[String]) {
     if bundles.is_empty() {
-        println!("Bundles: (none)");
+        say!("Bundles: (none)");
     } else {
-        println!("Bundles:");
+        say!("Bundles:");
         for id in bundles {
             if let Some(bundle) = bundles::find(id) {
-                println!("  - {} [{}]", bundle.name, bundle.id);
+                say!("  - {} [{}]", bundle.name, bundle.id);
             } else {
-                println!("  - {}", id);
+                say!("  - {}", id);
             }
         }
     }
 
     if paths.is_empty() {
-        println!("Explicit paths: (none)");
+        say!("Explicit paths: (none)");
     } else {
-        println!("Explicit paths:");
+        say!("Explicit paths:");
         for path in paths {
-            println!("  - {}", path);
+            say!("  - {}", path);
+        }
+    }
+}
+
+fn print_package_list(title: &str, packages: &[String]) {
+    if packages.is_empty() {
+        say!("{title}: (none)");
+    } else {
+        say!("{title}:");
+        for package in packages {
+            say!("  - {package}");
         }
     }
 }
@@ -834,15 +2028,14 @@ fn elephant_icon_path() -> Result<PathBuf> {
 fn remove_elephant_menu() -> Result<()> {
     let path = elephant_menu_path()?;
     remove_file_if_exists(&path)?;
-    if let Some(parent) = path.parent() {
-        if parent.is_dir()
-            && parent
-                .read_dir()
-                .map(|mut it| it.next().is_none())
-                .unwrap_or(false)
-        {
-            let _ = fs::remove_dir(parent);
-        }
+    if let Some(parent) = path.parent()
+        && parent.is_dir()
+        && parent
+            .read_dir()
+            .map(|mut it| it.next().is_none())
+            .unwrap_or(false)
+    {
+        let _ = fs::remove_dir(parent);
     }
     Ok(())
 }
@@ -0,0 +1,88 @@
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use which::which;
+
+use crate::config::NotificationsConfig;
+use crate::output;
+
+/// Result of a backup or restore, as far as notifications are concerned.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// Something was saved or restored; carries the ids of the bundles involved.
+    Changed(Vec<String>),
+    /// The run completed without changing anything.
+    Unchanged,
+    /// The run failed; carries the error text including any hint.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Urgency::Normal => "normal",
+            Urgency::Critical => "critical",
+        }
+    }
+}
+
+/// Delivers desktop notifications so the transport can be swapped out.
+pub trait Notifier {
+    fn send(&self, urgency: Urgency, summary: &str, body: &str) -> Result<()>;
+}
+
+/// Notifier that shells out to `notify-send`, doing nothing when it is not installed.
+pub struct NotifySend;
+
+impl Notifier for NotifySend {
+    fn send(&self, urgency: Urgency, summary: &str, body: &str) -> Result<()> {
+        if which("notify-send").is_err() {
+            return Ok(());
+        }
+        let status = Command::new("notify-send")
+            .args(["--app-name", "omarchy-syncd", "--urgency", urgency.as_str()])
+            .arg(summary)
+            .arg(body)
+            .stdout(output::child_stdout())
+            .status()
+            .context("Failed to execute notify-send")?;
+        if !status.success() {
+            anyhow::bail!("notify-send exited with status {:?}", status.code());
+        }
+        Ok(())
+    }
+}
+
+/// Notify about `outcome` of `operation` ("Backup", "Restore") if the config asks for it.
+pub fn report(
+    cfg: &NotificationsConfig,
+    notifier: &dyn Notifier,
+    operation: &str,
+    outcome: &Outcome,
+) -> Result<()> {
+    match outcome {
+        Outcome::Changed(bundles) if cfg.on_success => {
+            let body = if bundles.is_empty() {
+                "Snapshot updated.".to_string()
+            } else {
+                format!("Changed: {}", bundles.join(", "))
+            };
+            notifier.send(Urgency::Normal, &format!("{operation} complete"), &body)
+        }
+        Outcome::Unchanged if cfg.on_no_changes => notifier.send(
+            Urgency::Normal,
+            &format!("{operation} complete"),
+            "Nothing changed.",
+        ),
+        Outcome::Failed(error) if cfg.on_failure => {
+            notifier.send(Urgency::Critical, &format!("{operation} failed"), error)
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::git;
+use crate::selector::{self, InputRequired};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// How commands report their results on stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable progress messages.
+    #[default]
+    Text,
+    /// A single JSON document per command; progress messages go to stderr.
+    Json,
+}
+
+pub fn set_format(format: OutputFormat) {
+    JSON.store(format == OutputFormat::Json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Print a progress message: to stdout normally, to stderr under `--output json` so stdout
+/// only carries the JSON document.
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Stdout for child processes whose output is shown to the user (hooks, package managers):
+/// redirected to stderr under `--output json` so it cannot end up in the JSON document.
+pub fn child_stdout() -> Stdio {
+    if is_json() {
+        Stdio::from(io::stderr())
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// Show a prompt without a newline, keeping stdout clean under `--output json`.
+pub fn print_prompt(text: &str) -> io::Result<()> {
+    if is_json() {
+        eprint!("{text}");
+        io::stderr().flush()
+    } else {
+        print!("{text}");
+        io::stdout().flush()
+    }
+}
+
+/// A path that was left alone, and why.
+#[derive(Debug, Clone, Serialize)]
+pub struct Skipped {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Print the JSON document for a command that ran to completion. It is marked `"ok": true`
+/// unless the report already says otherwise (e.g. `doctor` with failed checks).
+pub fn emit(report: &impl Serialize) -> anyhow::Result<()> {
+    let mut value = serde_json::to_value(report)?;
+    if let Value::Object(map) = &mut value {
+        map.entry("ok").or_insert(Value::Bool(true));
+    }
+    println!("{}", serde_json::to_string_pretty(&value)?);
+    Ok(())
+}
+
+/// A command that ran to the end but found problems. Under `--output json` its full report
+/// is still the output, with `"ok": false`; in text mode only `summary` is printed.
+#[derive(Debug)]
+pub struct FailedReport {
+    pub report: Value,
+    summary: String,
+}
+
+impl FailedReport {
+    pub fn new(report: Value, summary: impl Into<String>) -> Self {
+        Self {
+            report,
+            summary: summary.into(),
+        }
+    }
+}
+
+impl fmt::Display for FailedReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.summary)
+    }
+}
+
+impl std::error::Error for FailedReport {}
+
+/// Print the JSON document for a failed command.
+pub fn emit_error(err: &anyhow::Error) {
+    let git_err = git::find_error(err);
+    let input_required = selector::find_input_required(err).is_some();
+    let value = json!({
+        "ok": false,
+        "error": format!("{err:#}"),
+        "kind": git_err
+            .map(|e| e.kind.as_str())
+            .or(input_required.then_some("input_required")),
+        "hint": git_err
+            .and_then(|e| e.kind.hint())
+            .or(input_required.then(InputRequired::hint)),
+    });
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string())
+    );
+}
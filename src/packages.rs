@@ -0,0 +1,249 @@
+use std::{
+    collections::BTreeSet,
+    fmt, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use which::which;
+
+use crate::fs_ops;
+use crate::output;
+use crate::say;
+use crate::selector;
+
+const PACKAGE_MANIFEST_FILE: &str = "packages.json";
+const AUR_HELPERS: &[&str] = &["paru", "yay"];
+
+/// Explicitly installed packages recorded alongside a snapshot.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageManifest {
+    /// Packages from the sync repositories (`pacman -Qqe` minus foreign packages).
+    pub native: Vec<String>,
+    /// Foreign packages, typically from the AUR (`pacman -Qqm`).
+    pub foreign: Vec<String>,
+}
+
+/// Packages recorded in a manifest that are missing on this machine.
+#[derive(Debug, Default)]
+pub struct PackageDiff {
+    pub missing_native: Vec<String>,
+    pub missing_foreign: Vec<String>,
+}
+
+impl PackageDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing_native.is_empty() && self.missing_foreign.is_empty()
+    }
+}
+
+/// A program that ran but exited non-zero, as reported by [`CommandRunner::output`].
+#[derive(Debug)]
+pub struct CommandFailed {
+    pub command: String,
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl fmt::Display for CommandFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} exited with status {:?}: {}",
+            self.command,
+            self.code,
+            self.stderr.trim()
+        )
+    }
+}
+
+impl std::error::Error for CommandFailed {}
+
+/// Runs external programs so package operations can be stubbed out.
+pub trait CommandRunner {
+    /// Run `program` and return its stdout, failing on a non-zero exit.
+    fn output(&self, program: &str, args: &[&str]) -> Result<String>;
+    /// Run `program` attached to the terminal, failing on a non-zero exit.
+    fn run(&self, program: &str, args: &[&str]) -> Result<()>;
+    /// Whether `program` can be found.
+    fn available(&self, program: &str) -> bool;
+}
+
+/// Runner that resolves programs from `PATH`.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn output(&self, program: &str, args: &[&str]) -> Result<String> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to execute {program}"))?;
+        if !output.status.success() {
+            return Err(CommandFailed {
+                command: format!("{} {}", program, args.join(" ")),
+                code: output.status.code(),
+                stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            }
+            .into());
+        }
+        String::from_utf8(output.stdout)
+            .with_context(|| format!("{program} produced invalid UTF-8 output"))
+    }
+
+    fn run(&self, program: &str, args: &[&str]) -> Result<()> {
+        let status = Command::new(program)
+            .args(args)
+            .stdout(output::child_stdout())
+            .status()
+            .with_context(|| format!("Failed to execute {program}"))?;
+        if !status.success() {
+            anyhow::bail!(
+                "{} {} exited with status {:?}",
+                program,
+                args.join(" "),
+                status.code()
+            );
+        }
+        Ok(())
+    }
+
+    fn available(&self, program: &str) -> bool {
+        which(program).is_ok()
+    }
+}
+
+pub fn manifest_path(repo_dir: &Path) -> PathBuf {
+    fs_ops::repo_metadata_dir(repo_dir).join(PACKAGE_MANIFEST_FILE)
+}
+
+/// Query pacman for explicitly installed and foreign packages.
+pub fn capture(runner: &dyn CommandRunner) -> Result<PackageManifest> {
+    let explicit = parse_list(&runner.output("pacman", &["-Qqe"])?);
+    // pacman exits 1 without output when no foreign packages are installed.
+    let foreign = match runner.output("pacman", &["-Qqm"]) {
+        Ok(out) => parse_list(&out),
+        Err(err)
+            if err.downcast_ref::<CommandFailed>().is_some_and(|failed| {
+                failed.code == Some(1) && failed.stdout.trim().is_empty()
+            }) =>
+        {
+            BTreeSet::new()
+        }
+        Err(err) => return Err(err),
+    };
+    let native = explicit.difference(&foreign).cloned().collect();
+    Ok(PackageManifest {
+        native,
+        foreign: foreign.into_iter().collect(),
+    })
+}
+
+/// Record the package list in the snapshot, skipping machines without pacman.
+pub fn snapshot(runner: &dyn CommandRunner, repo_dir: &Path) -> Result<()> {
+    if !runner.available("pacman") {
+        say!("Skipping package list because pacman is not available.");
+        return Ok(());
+    }
+    let manifest = capture(runner)?;
+    let path = manifest_path(repo_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating metadata directory {}", parent.display()))?;
+    }
+    let data = serde_json::to_vec_pretty(&manifest)?;
+    fs::write(&path, data)
+        .with_context(|| format!("Failed writing package list {}", path.display()))?;
+    Ok(())
+}
+
+pub fn load_manifest(repo_dir: &Path) -> Result<PackageManifest> {
+    let path = manifest_path(repo_dir);
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == ErrorKind::NotFound => anyhow::bail!(
+            "The latest snapshot has no package list. Run 'omarchy-syncd backup' on a machine with pacman first."
+        ),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed reading package list {}", path.display()));
+        }
+    };
+    serde_json::from_str(&data).context("Failed parsing package list")
+}
+
+/// Compare a recorded manifest with what is installed locally.
+pub fn diff(runner: &dyn CommandRunner, manifest: &PackageManifest) -> Result<PackageDiff> {
+    let installed = parse_list(&runner.output("pacman", &["-Qq"])?);
+    let missing = |packages: &[String]| -> Vec<String> {
+        packages
+            .iter()
+            .filter(|pkg| !installed.contains(*pkg))
+            .cloned()
+            .collect()
+    };
+    Ok(PackageDiff {
+        missing_native: missing(&manifest.native),
+        missing_foreign: missing(&manifest.foreign),
+    })
+}
+
+/// Install missing packages with pacman and the configured (or detected) AUR helper.
+pub fn install(
+    runner: &dyn CommandRunner,
+    diff: &PackageDiff,
+    aur_helper: Option<&str>,
+) -> Result<()> {
+    // Without a terminal to answer them, sudo's password prompt and the confirmations
+    // would hang or fail later; make them fail fast or skip them instead.
+    let batch = selector::is_non_interactive();
+    if !diff.missing_native.is_empty() {
+        let mut args = if batch { vec!["-n"] } else { Vec::new() };
+        args.extend(["pacman", "-S", "--needed"]);
+        if batch {
+            args.push("--noconfirm");
+        }
+        args.extend(diff.missing_native.iter().map(String::as_str));
+        runner
+            .run("sudo", &args)
+            .context("Failed installing packages with pacman")?;
+    }
+
+    if !diff.missing_foreign.is_empty() {
+        let helper = match aur_helper {
+            Some(helper) => helper.to_string(),
+            None => AUR_HELPERS
+                .iter()
+                .find(|helper| runner.available(helper))
+                .map(|helper| helper.to_string())
+                .with_context(|| {
+                    format!(
+                        "No AUR helper found for {}. Install paru or yay, or set `aur_helper` under [packages].",
+                        diff.missing_foreign.join(", ")
+                    )
+                })?,
+        };
+        let mut args = vec!["-S", "--needed"];
+        if batch {
+            args.push("--noconfirm");
+        }
+        args.extend(diff.missing_foreign.iter().map(String::as_str));
+        runner
+            .run(&helper, &args)
+            .with_context(|| format!("Failed installing AUR packages with {helper}"))?;
+    }
+
+    Ok(())
+}
+
+fn parse_list(raw: &str) -> BTreeSet<String> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
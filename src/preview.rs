@@ -0,0 +1,231 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use similar::TextDiff;
+use walkdir::WalkDir;
+
+use crate::fs_ops;
+use crate::state::{self, SyncState};
+use crate::waybar;
+
+/// Changed files listed before the rest are summarised as a count.
+const MAX_LISTED: usize = 20;
+/// Diff lines shown across all changed files.
+const MAX_DIFF_LINES: usize = 80;
+
+/// Describes a tracked path for the selector's preview pane: whether it exists locally and
+/// in the last synced snapshot, how big it is, when it was last backed up, and how it differs.
+pub struct Previewer {
+    home: PathBuf,
+    /// Last synced snapshot, if one can be read without fetching.
+    repo_dir: Option<PathBuf>,
+    state: SyncState,
+}
+
+impl Previewer {
+    pub fn new(repo_dir: Option<PathBuf>) -> Result<Self> {
+        let home = std::env::var("HOME").context("HOME environment variable not set")?;
+        Ok(Self {
+            home: PathBuf::from(home),
+            repo_dir,
+            // A missing or unreadable cache only costs the timestamps.
+            state: state::load().unwrap_or_default(),
+        })
+    }
+
+    /// Preview text for the tracked path (or sub-path) `raw`.
+    pub fn render(&self, raw: &str) -> String {
+        match self.try_render(raw) {
+            Ok(text) => text,
+            Err(err) => format!("{raw}\n\nNo preview: {err:#}"),
+        }
+    }
+
+    fn try_render(&self, raw: &str) -> Result<String> {
+        let rel = fs_ops::home_relative(raw)?;
+        let local = self.home.join(&rel);
+        let mut lines = vec![raw.to_string(), String::new()];
+        lines.push(format!("Local: {}", describe(&local)?));
+        let repo = self.repo_dir.as_ref().map(|dir| dir.join(&rel));
+        match &repo {
+            Some(repo) => lines.push(format!("Repo:  {}", describe(repo)?)),
+            None => lines.push("Repo:  unknown until the next sync".to_string()),
+        }
+        lines.push(match self.state.last_backup {
+            Some(at) => format!(
+                "Last backup: {} ago",
+                waybar::format_age(state::now().saturating_sub(at))
+            ),
+            None => "Last backup: never".to_string(),
+        });
+        if self.state.paths.contains_key(raw) {
+            let modified = !self.state.modified_paths(&[raw.to_string()])?.is_empty();
+            lines.push(format!(
+                "Since last sync: {}",
+                if modified { "modified" } else { "unchanged" }
+            ));
+        }
+
+        if let Some(repo) = &repo {
+            lines.push(String::new());
+            lines.extend(changes(&local, repo)?);
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+fn describe(path: &Path) -> Result<String> {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return Ok("missing".to_string());
+    };
+    if meta.file_type().is_symlink() && !path.is_dir() {
+        let target = fs::read_link(path)?;
+        return Ok(format!("symlink to {}", target.display()));
+    }
+    if !path.is_dir() {
+        return Ok(format!("file, {}", format_size(fs::metadata(path)?.len())));
+    }
+    let files = list_files(path)?;
+    let mut size = 0;
+    for file in &files {
+        size += fs::metadata(path.join(file)).map_or(0, |meta| meta.len());
+    }
+    Ok(format!(
+        "directory, {} file(s), {}",
+        files.len(),
+        format_size(size)
+    ))
+}
+
+/// `M`/`A`/`D` lines for files that differ (seen from the local side), then text diffs.
+fn changes(local: &Path, repo: &Path) -> Result<Vec<String>> {
+    let (local_files, repo_files) = if local.is_dir() || repo.is_dir() {
+        (list_files(local)?, list_files(repo)?)
+    } else {
+        let present = |path: &Path| -> Vec<PathBuf> {
+            if path.is_file() {
+                vec![PathBuf::new()]
+            } else {
+                Vec::new()
+            }
+        };
+        (present(local), present(repo))
+    };
+
+    let mut listed = Vec::new();
+    let mut modified = Vec::new();
+    for file in &local_files {
+        if !repo_files.contains(file) {
+            listed.push(format!("A {}", display(local, file)));
+        } else if fs::read(member(local, file)).ok() != fs::read(member(repo, file)).ok() {
+            listed.push(format!("M {}", display(local, file)));
+            modified.push(file.clone());
+        }
+    }
+    for file in &repo_files {
+        if !local_files.contains(file) {
+            listed.push(format!("D {}", display(local, file)));
+        }
+    }
+    if listed.is_empty() {
+        return Ok(vec!["Local and repo copies match.".to_string()]);
+    }
+
+    let mut lines = vec!["Changes (local against repo):".to_string()];
+    let hidden = listed.len().saturating_sub(MAX_LISTED);
+    lines.extend(
+        listed
+            .into_iter()
+            .take(MAX_LISTED)
+            .map(|line| format!("  {line}")),
+    );
+    if hidden > 0 {
+        lines.push(format!("  … and {hidden} more"));
+    }
+
+    let mut budget = MAX_DIFF_LINES;
+    for file in modified {
+        let (Ok(old), Ok(new)) = (
+            fs::read_to_string(member(repo, &file)),
+            fs::read_to_string(member(local, &file)),
+        ) else {
+            continue;
+        };
+        let name = display(local, &file);
+        let diff = TextDiff::from_lines(&old, &new)
+            .unified_diff()
+            .context_radius(2)
+            .header(&format!("repo/{name}"), &format!("local/{name}"))
+            .to_string();
+        lines.push(String::new());
+        for line in diff.lines() {
+            if budget == 0 {
+                lines.push("…".to_string());
+                return Ok(lines);
+            }
+            lines.push(line.to_string());
+            budget -= 1;
+        }
+    }
+    Ok(lines)
+}
+
+/// Files under `root` relative to it, skipping `.git`; empty when `root` is not a directory.
+fn list_files(root: &Path) -> Result<Vec<PathBuf>> {
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .follow_links(true)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.path().strip_prefix(root)?.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+/// `file` (as listed by `changes`) inside `root`; an empty name stands for `root` itself, and
+/// joining it would add a trailing slash that files cannot be opened with.
+fn member(root: &Path, file: &Path) -> PathBuf {
+    if file.as_os_str().is_empty() {
+        root.to_path_buf()
+    } else {
+        root.join(file)
+    }
+}
+
+/// A file's name for the change list: its path inside the tracked directory, or the file name.
+fn display(local: &Path, file: &Path) -> String {
+    if file.as_os_str().is_empty() {
+        local
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+    } else {
+        file.display().to_string()
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+use crate::packages::CommandRunner;
+
+pub const SERVICE_NAME: &str = "omarchy-syncd-backup.service";
+pub const TIMER_NAME: &str = "omarchy-syncd-backup.timer";
+const MANAGED_MARKER: &str = "# Managed by omarchy-syncd";
+
+/// State of the scheduled-backup timer as reported by `status`.
+#[derive(Debug)]
+pub struct ScheduleStatus {
+    /// Interval written into the timer unit, if it is installed.
+    pub every: Option<String>,
+    /// `systemctl --user is-active` output for the timer, if systemctl is available.
+    pub active: Option<String>,
+}
+
+/// Directory systemd scans for user units.
+pub fn unit_dir() -> Result<PathBuf> {
+    let base = if let Some(xdg) = std::env::var_os("XDG_CONFIG_HOME") {
+        PathBuf::from(xdg)
+    } else {
+        let home = std::env::var_os("HOME").context("HOME environment variable not set")?;
+        PathBuf::from(home).join(".config")
+    };
+    Ok(base.join("systemd/user"))
+}
+
+/// Write the service and timer units and start the timer.
+pub fn enable(runner: &dyn CommandRunner, exe: &Path, every: Duration) -> Result<()> {
+    ensure_systemctl(runner)?;
+    let dir = unit_dir()?;
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed creating unit directory {}", dir.display()))?;
+
+    let service = dir.join(SERVICE_NAME);
+    fs::write(&service, service_unit(exe))
+        .with_context(|| format!("Failed writing {}", service.display()))?;
+    let timer = dir.join(TIMER_NAME);
+    fs::write(&timer, timer_unit(every))
+        .with_context(|| format!("Failed writing {}", timer.display()))?;
+
+    systemctl(runner, &["daemon-reload"])?;
+    systemctl(runner, &["enable", "--now", TIMER_NAME])?;
+    Ok(())
+}
+
+/// Stop the timer and delete both units. Returns false if nothing was installed.
+pub fn disable(runner: &dyn CommandRunner) -> Result<bool> {
+    let dir = unit_dir()?;
+    let timer = dir.join(TIMER_NAME);
+    let service = dir.join(SERVICE_NAME);
+    if !is_managed(&timer) && !is_managed(&service) {
+        return Ok(false);
+    }
+
+    if runner.available("systemctl") {
+        systemctl(runner, &["disable", "--now", TIMER_NAME])?;
+    }
+    for path in [&timer, &service] {
+        if is_managed(path) {
+            fs::remove_file(path).with_context(|| format!("Failed removing {}", path.display()))?;
+        }
+    }
+    if runner.available("systemctl") {
+        systemctl(runner, &["daemon-reload"])?;
+    }
+    Ok(true)
+}
+
+pub fn status(runner: &dyn CommandRunner) -> Result<ScheduleStatus> {
+    let timer = unit_dir()?.join(TIMER_NAME);
+    let every = match fs::read_to_string(&timer) {
+        Ok(data) => data
+            .lines()
+            .find_map(|line| line.strip_prefix("OnUnitActiveSec="))
+            .map(str::to_string),
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed reading {}", timer.display()));
+        }
+    };
+
+    // `is-active` exits non-zero for anything but an active unit.
+    let active = if every.is_some() && runner.available("systemctl") {
+        let state = match runner.output("systemctl", &["--user", "is-active", TIMER_NAME]) {
+            Ok(out) => out.trim().to_string(),
+            Err(_) => "inactive".to_string(),
+        };
+        Some(state)
+    } else {
+        None
+    };
+
+    Ok(ScheduleStatus { every, active })
+}
+
+/// Render a duration the way systemd time spans are usually written (`90s`, `15m`, `1h`, `2d`).
+pub fn format_interval(every: Duration) -> String {
+    let secs = every.as_secs();
+    for (unit, size) in [("d", 86_400), ("h", 3_600), ("m", 60)] {
+        if secs.is_multiple_of(size) {
+            return format!("{}{unit}", secs / size);
+        }
+    }
+    format!("{secs}s")
+}
+
+fn service_unit(exe: &Path) -> String {
+    format!(
+        "{MANAGED_MARKER}\n\
+         [Unit]\n\
+         Description=omarchy-syncd scheduled backup\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart=\"{}\" backup --all --no-ui\n",
+        exe.display()
+    )
+}
+
+fn timer_unit(every: Duration) -> String {
+    let every = format_interval(every);
+    format!(
+        "{MANAGED_MARKER}\n\
+         [Unit]\n\
+         Description=Run omarchy-syncd backup every {every}\n\
+         \n\
+         [Timer]\n\
+         OnBootSec=5min\n\
+         OnUnitActiveSec={every}\n\
+         Unit={SERVICE_NAME}\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n"
+    )
+}
+
+fn is_managed(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|data| data.starts_with(MANAGED_MARKER))
+}
+
+fn ensure_systemctl(runner: &dyn CommandRunner) -> Result<()> {
+    if !runner.available("systemctl") {
+        anyhow::bail!("systemctl was not found; scheduled backups need systemd.");
+    }
+    Ok(())
+}
+
+fn systemctl(runner: &dyn CommandRunner, args: &[&str]) -> Result<()> {
+    let mut full = vec!["--user"];
+    full.extend_from_slice(args);
+    runner
+        .output("systemctl", &full)
+        .map(|_| ())
+        .with_context(|| format!("Failed running systemctl --user {}", args.join(" ")))
+}
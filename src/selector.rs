@@ -1,110 +1,655 @@
-use anyhow::{Result, anyhow};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+    io::{self, ErrorKind, IsTerminal, Write},
+    path::Path,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 use skim::prelude::*;
+use which::which;
 
-#[derive(Clone)]
-struct SelectableItem {
-    id: String,
-    display: String,
+use crate::config::UiConfig;
+use crate::output;
+use crate::say;
+
+/// Turns on non-interactive mode like `--non-interactive` when set to `1`, `true` or `yes`.
+pub const NON_INTERACTIVE_ENV: &str = "OMARCHY_SYNCD_NONINTERACTIVE";
+/// Exit status of a command that stopped because it needed input in non-interactive mode.
+pub const INPUT_REQUIRED_EXIT: i32 = 3;
+
+static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Never prompt from now on (the global `--non-interactive` flag).
+pub fn set_non_interactive() {
+    NON_INTERACTIVE.store(true, Ordering::Relaxed);
+}
+
+pub fn is_non_interactive() -> bool {
+    NON_INTERACTIVE.load(Ordering::Relaxed)
+        || std::env::var(NON_INTERACTIVE_ENV)
+            .is_ok_and(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// Input a command needed but was not allowed to ask for.
+#[derive(Debug)]
+pub struct InputRequired {
+    what: String,
+}
+
+impl InputRequired {
+    fn new(what: impl Into<String>) -> Self {
+        Self { what: what.into() }
+    }
+
+    pub fn hint() -> &'static str {
+        "Pass the answer on the command line (for example --yes, --all or --path), or run without --non-interactive."
+    }
+}
+
+impl fmt::Display for InputRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} needs input, but prompts are disabled (--non-interactive)",
+            self.what
+        )
+    }
 }
 
-impl SelectableItem {
-    fn new(id: impl Into<String>, display: impl Into<String>) -> Self {
+impl std::error::Error for InputRequired {}
+
+/// Fail with [`InputRequired`] in non-interactive mode, before anything waits for `what`.
+pub fn require_input(what: &str) -> Result<()> {
+    if is_non_interactive() {
+        return Err(InputRequired::new(what).into());
+    }
+    Ok(())
+}
+
+/// The missing-input failure behind `err`, if any, looking through added context.
+pub fn find_input_required(err: &anyhow::Error) -> Option<&InputRequired> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<InputRequired>())
+}
+
+/// The user backed out of a prompt; nothing failed, so it is neither notified nor recorded.
+#[derive(Debug)]
+pub struct Cancelled {
+    message: String,
+}
+
+impl Cancelled {
+    pub fn new(message: impl Into<String>) -> Self {
         Self {
-            id: id.into(),
-            display: display.into(),
+            message: message.into(),
+        }
+    }
+
+    /// A selector closed without picking anything.
+    pub fn selection() -> Self {
+        Self::new("Selection cancelled")
+    }
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Whether `err` means the user cancelled, or could not be asked under `--non-interactive`,
+/// rather than the operation failing.
+pub fn is_cancelled(err: &anyhow::Error) -> bool {
+    find_input_required(err).is_some()
+        || err
+            .chain()
+            .any(|cause| cause.downcast_ref::<Cancelled>().is_some())
+}
+
+/// Renders the preview pane for the highlighted choice, given its id.
+pub type Preview = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Which implementation asks the user to pick entries, selected via `ui.selector` or
+/// `--selector`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectorKind {
+    /// Fuzzy finder in the terminal.
+    #[default]
+    Skim,
+    /// A dmenu-style launcher (walker, fuzzel, wofi, rofi); needs no terminal.
+    Dmenu,
+    /// Numbered list answered on standard input, for terminals skim cannot drive.
+    Prompt,
+}
+
+impl SelectorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SelectorKind::Skim => "skim",
+            SelectorKind::Dmenu => "dmenu",
+            SelectorKind::Prompt => "prompt",
+        }
+    }
+}
+
+pub struct Choice {
+    pub id: String,
+    pub label: String,
+}
+
+/// Everything a backend needs to show one list of choices.
+pub struct Menu<'a> {
+    pub title: &'a str,
+    /// Key help shown above the list by skim; the other backends explain their own input.
+    pub header: &'a str,
+    pub choices: &'a [Choice],
+    /// Extra skim key bindings, such as `ctrl-o:accept`.
+    pub binds: &'a [&'a str],
+    /// Ids selected when the list opens (multi-select only).
+    pub preselected: &'a [String],
+    pub preview: Option<Preview>,
+}
+
+impl<'a> Menu<'a> {
+    pub fn new(title: &'a str, header: &'a str, choices: &'a [Choice]) -> Self {
+        Self {
+            title,
+            header,
+            choices,
+            binds: &[],
+            preselected: &[],
+            preview: None,
+        }
+    }
+
+    pub fn binds(mut self, binds: &'a [&'a str]) -> Self {
+        self.binds = binds;
+        self
+    }
+
+    pub fn preselect(mut self, ids: &'a [String]) -> Self {
+        self.preselected = ids;
+        self
+    }
+
+    pub fn preview(mut self, preview: Preview) -> Self {
+        self.preview = Some(preview);
+        self
+    }
+
+    fn preselected_indices(&self) -> BTreeSet<usize> {
+        (0..self.choices.len())
+            .filter(|index| self.preselected.contains(&self.choices[*index].id))
+            .collect()
+    }
+
+    fn ids(&self, indices: BTreeSet<usize>) -> Vec<String> {
+        indices
+            .into_iter()
+            .map(|index| self.choices[index].id.clone())
+            .collect()
+    }
+}
+
+/// Asks the user to pick among choices. Cancelling is an error, so callers can simply `?` it.
+pub trait Selector {
+    /// Whether the backend can ask right now; callers skip selection when it cannot.
+    fn available(&self) -> bool;
+    /// Ids of the chosen entries, possibly none.
+    fn multi_select(&self, menu: &Menu) -> Result<Vec<String>>;
+    /// Id of the chosen entry.
+    fn single_select(&self, menu: &Menu) -> Result<String>;
+    fn confirm(&self, question: &str) -> Result<bool> {
+        prompt_yes_no(question)
+    }
+}
+
+/// The backend configured under [ui], after `--selector` and `$OMARCHY_SYNCD_SELECTOR`.
+pub fn open(ui: &UiConfig) -> Box<dyn Selector> {
+    if is_non_interactive() {
+        return Box::new(NoSelector);
+    }
+    match ui.selector {
+        SelectorKind::Skim => Box::new(SkimSelector),
+        SelectorKind::Dmenu => Box::new(DmenuSelector {
+            command: ui.dmenu_command.clone(),
+        }),
+        SelectorKind::Prompt => Box::new(PromptSelector),
+    }
+}
+
+/// Ask a yes/no question on the terminal until it gets an answer.
+pub fn prompt_yes_no(question: &str) -> Result<bool> {
+    require_input(&format!("\"{question}\""))?;
+    loop {
+        let answer = read_answer(&format!("{question} [Y/n]: "))?;
+        match answer.to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => say!("Please answer 'y' or 'n'."),
+        }
+    }
+}
+
+/// Ask for a line of text on the terminal.
+pub fn prompt_string(prompt: &str) -> Result<String> {
+    read_answer(&format!("{prompt}: "))
+}
+
+fn read_answer(prompt: &str) -> Result<String> {
+    require_input(&format!("\"{}\"", prompt.trim_end_matches([' ', ':', '>'])))?;
+    output::print_prompt(prompt)?;
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        bail!("No answer given (end of input)");
+    }
+    Ok(line.trim().to_string())
+}
+
+fn toggle(marked: &mut BTreeSet<usize>, indices: impl IntoIterator<Item = usize>) {
+    for index in indices {
+        if !marked.remove(&index) {
+            marked.insert(index);
         }
     }
 }
 
+#[derive(Clone)]
+struct SelectableItem {
+    id: String,
+    display: String,
+    preview: Option<Preview>,
+}
+
 impl SkimItem for SelectableItem {
     fn text(&self) -> Cow<'_, str> {
         Cow::Borrowed(self.display.as_str())
     }
 
+    fn preview(&self, _context: PreviewContext) -> ItemPreview {
+        match &self.preview {
+            Some(render) => ItemPreview::Text(render(&self.id)),
+            None => ItemPreview::Global,
+        }
+    }
+
     fn output(&self) -> Cow<'_, str> {
         Cow::Borrowed(self.id.as_str())
     }
 }
 
-pub struct Choice {
-    pub id: String,
-    pub label: String,
+/// Marks the items whose id is in the set as selected when the picker opens.
+struct Preselect(HashSet<String>);
+
+impl skim::Selector for Preselect {
+    fn should_select(&self, _index: usize, item: &dyn SkimItem) -> bool {
+        self.0.contains(item.output().as_ref())
+    }
 }
 
-pub fn multi_select(
-    prompt: &str,
-    header: &str,
-    choices: &[Choice],
-    extra_binds: &[&str],
-) -> Result<Vec<String>> {
-    if choices.is_empty() {
-        return Ok(Vec::new());
+/// Stands in for the configured backend in non-interactive mode: it can never ask.
+struct NoSelector;
+
+impl Selector for NoSelector {
+    fn available(&self) -> bool {
+        false
+    }
+
+    fn multi_select(&self, menu: &Menu) -> Result<Vec<String>> {
+        Err(InputRequired::new(format!("Choosing {}", menu.title.to_lowercase())).into())
+    }
+
+    fn single_select(&self, menu: &Menu) -> Result<String> {
+        Err(InputRequired::new(format!("The {} menu", menu.title)).into())
     }
+}
+
+/// Fuzzy finder drawn in the terminal.
+pub struct SkimSelector;
+
+impl SkimSelector {
+    fn run(&self, menu: &Menu, multi: bool) -> Result<Vec<String>> {
+        let mut binds: Vec<&str> = Vec::new();
+        if multi {
+            binds.extend(["tab:toggle", "shift-tab:select-all"]);
+        }
+        binds.extend(menu.binds.iter().copied());
+        let selector: Option<Rc<dyn skim::Selector>> = if menu.preselected.is_empty() {
+            None
+        } else {
+            Some(Rc::new(Preselect(
+                menu.preselected.iter().cloned().collect(),
+            )))
+        };
+        let prompt = format!("{} (type to filter)> ", menu.title);
+
+        // An empty preview command only turns the pane on; the items render their own text.
+        let options = SkimOptionsBuilder::default()
+            .multi(multi)
+            .selector(selector)
+            .prompt(Some(&prompt))
+            .header(Some(menu.header))
+            .bind(binds)
+            .preview(menu.preview.as_ref().map(|_| ""))
+            .preview_window(Some("right:50%:wrap"))
+            .build()
+            .map_err(|err| anyhow!("Failed creating skim options: {err}"))?;
 
-    let mut binds: Vec<&str> = vec!["tab:toggle", "shift-tab:select-all"];
-    binds.extend(extra_binds.iter().copied());
+        let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
+        for choice in menu.choices {
+            let item = SelectableItem {
+                id: choice.id.clone(),
+                display: choice.label.clone(),
+                preview: menu.preview.clone(),
+            };
+            tx.send(Arc::new(item))
+                .map_err(|err| anyhow!("Failed queueing selection item: {err}"))?;
+        }
+        drop(tx);
 
-    let options = SkimOptionsBuilder::default()
-        .multi(true)
-        .prompt(Some(prompt))
-        .header(Some(header))
-        .bind(binds)
-        .build()
-        .map_err(|err| anyhow!("Failed creating skim options: {err}"))?;
+        let output =
+            Skim::run_with(&options, Some(rx)).ok_or_else(|| anyhow!("Selection aborted"))?;
+        if output.is_abort {
+            return Err(Cancelled::selection().into());
+        }
+        Ok(output
+            .selected_items
+            .iter()
+            .map(|item| item.output().into_owned())
+            .collect())
+    }
+}
 
-    let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
-    for choice in choices {
-        tx.send(Arc::new(SelectableItem::new(&choice.id, &choice.label)))
-            .map_err(|err| anyhow!("Failed queueing selection item: {err}"))?;
+impl Selector for SkimSelector {
+    fn available(&self) -> bool {
+        io::stdin().is_terminal() && io::stdout().is_terminal()
     }
-    drop(tx);
 
-    let output = Skim::run_with(&options, Some(rx)).ok_or_else(|| anyhow!("Selection aborted"))?;
-    if output.is_abort {
-        anyhow::bail!("Selection cancelled");
+    fn multi_select(&self, menu: &Menu) -> Result<Vec<String>> {
+        if menu.choices.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.run(menu, true)
     }
 
-    let selected = output
-        .selected_items
-        .iter()
-        .map(|item| {
-            let raw = item.output();
-            raw.as_ref().to_string()
-        })
-        .collect();
-    Ok(selected)
+    fn single_select(&self, menu: &Menu) -> Result<String> {
+        if menu.choices.is_empty() {
+            bail!("No options available");
+        }
+        self.run(menu, false)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Cancelled::selection().into())
+    }
 }
 
-pub fn single_select(
-    prompt: &str,
-    header: &str,
-    choices: &[Choice],
-    extra_binds: &[&str],
-) -> Result<String> {
-    if choices.is_empty() {
-        anyhow::bail!("No options available");
+/// Numbered list answered by typing entry numbers.
+pub struct PromptSelector;
+
+impl PromptSelector {
+    fn list(&self, menu: &Menu, marked: Option<&BTreeSet<usize>>) {
+        say!("{}:", menu.title);
+        for (index, choice) in menu.choices.iter().enumerate() {
+            let mark = match marked {
+                Some(marked) if marked.contains(&index) => "[x] ",
+                Some(_) => "[ ] ",
+                None => "",
+            };
+            say!("  {mark}{:>2}. {}", index + 1, choice.label);
+        }
     }
 
-    let binds: Vec<&str> = extra_binds.iter().copied().collect();
-    let options = SkimOptionsBuilder::default()
-        .multi(false)
-        .prompt(Some(prompt))
-        .header(Some(header))
-        .bind(binds)
-        .build()
-        .map_err(|err| anyhow!("Failed creating skim options: {err}"))?;
+    /// Print the preview for `?N`; returns `false` when `answer` is not a preview request.
+    fn show_preview(&self, menu: &Menu, answer: &str) -> bool {
+        let Some(number) = answer.strip_prefix('?') else {
+            return false;
+        };
+        match (parse_number(number, menu.choices.len()), &menu.preview) {
+            (Some(index), Some(render)) => say!("{}\n", render(&menu.choices[index].id)),
+            (Some(_), None) => say!("No preview available."),
+            (None, _) => say!("Unknown entry '{}'.", number.trim()),
+        }
+        true
+    }
+}
+
+impl Selector for PromptSelector {
+    fn available(&self) -> bool {
+        io::stdin().is_terminal()
+    }
+
+    fn multi_select(&self, menu: &Menu) -> Result<Vec<String>> {
+        if menu.choices.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut marked = menu.preselected_indices();
+        self.list(menu, Some(&marked));
+        loop {
+            say!(
+                "Type numbers to toggle (e.g. 1 3 5-7), 'a' for all, 'n' for none, '?N' to preview, 'l' to list, Enter to confirm, 'q' to cancel."
+            );
+            let answer = read_answer(&format!("{} ({} selected)> ", menu.title, marked.len()))?;
+            match answer.as_str() {
+                "" => return Ok(menu.ids(marked)),
+                "q" => return Err(Cancelled::selection().into()),
+                "a" => marked = (0..menu.choices.len()).collect(),
+                "n" => marked.clear(),
+                "l" => self.list(menu, Some(&marked)),
+                _ if self.show_preview(menu, &answer) => {}
+                _ => match parse_numbers(&answer, menu.choices.len()) {
+                    Some(indices) => toggle(&mut marked, indices),
+                    None => say!(
+                        "Could not read '{answer}'; use entry numbers between 1 and {}.",
+                        menu.choices.len()
+                    ),
+                },
+            }
+        }
+    }
 
-    let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
-    for choice in choices {
-        tx.send(Arc::new(SelectableItem::new(&choice.id, &choice.label)))
-            .map_err(|err| anyhow!("Failed queueing selection item: {err}"))?;
+    fn single_select(&self, menu: &Menu) -> Result<String> {
+        if menu.choices.is_empty() {
+            bail!("No options available");
+        }
+        self.list(menu, None);
+        loop {
+            let answer = read_answer("Number ('?N' previews, 'q' cancels)> ")?;
+            if answer == "q" {
+                return Err(Cancelled::selection().into());
+            }
+            if self.show_preview(menu, &answer) {
+                continue;
+            }
+            match parse_number(&answer, menu.choices.len()) {
+                Some(index) => return Ok(menu.choices[index].id.clone()),
+                None => say!("Enter a number between 1 and {}.", menu.choices.len()),
+            }
+        }
     }
-    drop(tx);
+}
+
+/// Zero-based index for a 1-based entry number.
+fn parse_number(raw: &str, len: usize) -> Option<usize> {
+    let number: usize = raw.trim().parse().ok()?;
+    (1..=len).contains(&number).then(|| number - 1)
+}
 
-    let output = Skim::run_with(&options, Some(rx)).ok_or_else(|| anyhow!("Selection aborted"))?;
-    if output.is_abort || output.selected_items.is_empty() {
-        anyhow::bail!("Selection cancelled");
+/// Zero-based indices for entry numbers and ranges separated by spaces or commas.
+fn parse_numbers(raw: &str, len: usize) -> Option<Vec<usize>> {
+    let mut indices = Vec::new();
+    for part in raw.split([' ', ',']).filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse_number(start, len)?, parse_number(end, len)?);
+                indices.extend(start.min(end)..=start.max(end));
+            }
+            None => indices.push(parse_number(part, len)?),
+        }
     }
+    Some(indices)
+}
 
-    Ok(output.selected_items[0].output().to_string())
+/// Launchers tried in order when `ui.dmenu_command` is not set, with the flags that put them
+/// in dmenu mode and introduce the prompt.
+const LAUNCHERS: &[(&str, &[&str])] = &[
+    ("walker", &["--dmenu", "-p"]),
+    ("fuzzel", &["--dmenu", "--prompt"]),
+    ("wofi", &["--dmenu", "--prompt"]),
+    ("rofi", &["-dmenu", "-p"]),
+];
+
+/// Picking the line starting with this confirms a multi-selection.
+const DONE: &str = "✓ Done";
+const SELECT_ALL: &str = "+ Select all";
+const SELECT_NONE: &str = "- Select none";
+
+/// dmenu protocol: entries go to the launcher's stdin and the chosen line comes back on stdout.
+/// Launchers pick one line at a time, so multi-select reopens the list with the entry toggled
+/// until "Done" is picked; launchers that return several lines at once are taken at their word.
+pub struct DmenuSelector {
+    /// Shell command from `ui.dmenu_command`; it gets the prompt in `$OMARCHY_SYNCD_PROMPT`.
+    command: Option<String>,
+}
+
+impl DmenuSelector {
+    fn command(&self, prompt: &str) -> Option<Command> {
+        if let Some(custom) = &self.command {
+            let mut command = Command::new("sh");
+            command
+                .args(["-c", custom])
+                .env("OMARCHY_SYNCD_PROMPT", prompt);
+            return Some(command);
+        }
+        let (program, args) = LAUNCHERS
+            .iter()
+            .find(|(program, _)| which(program).is_ok())?;
+        let mut command = Command::new(program);
+        command.args(*args).arg(prompt);
+        Some(command)
+    }
+
+    /// Show `lines` and return the chosen ones, or `None` when the launcher was dismissed.
+    fn run(&self, prompt: &str, lines: &[String]) -> Result<Option<Vec<String>>> {
+        let mut command = self
+            .command(prompt)
+            .ok_or_else(|| anyhow!("No dmenu launcher found; set `dmenu_command` under [ui]"))?;
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("Failed to start the dmenu launcher")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let input = lines.join("\n") + "\n";
+            // A launcher may exit before reading everything; its answer still counts.
+            if let Err(err) = stdin.write_all(input.as_bytes())
+                && err.kind() != ErrorKind::BrokenPipe
+            {
+                return Err(err).context("Failed writing choices to the dmenu launcher");
+            }
+        }
+        let output = child
+            .wait_with_output()
+            .context("Failed waiting for the dmenu launcher")?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        let picked: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok((!picked.is_empty()).then_some(picked))
+    }
+}
+
+impl Selector for DmenuSelector {
+    fn available(&self) -> bool {
+        match &self.command {
+            Some(custom) => custom
+                .split_whitespace()
+                .next()
+                .is_some_and(|program| Path::new(program).is_file() || which(program).is_ok()),
+            None => self.command("").is_some(),
+        }
+    }
+
+    fn multi_select(&self, menu: &Menu) -> Result<Vec<String>> {
+        if menu.choices.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut marked = menu.preselected_indices();
+        loop {
+            let entries: Vec<String> = menu
+                .choices
+                .iter()
+                .enumerate()
+                .map(|(index, choice)| {
+                    let mark = if marked.contains(&index) {
+                        "[x]"
+                    } else {
+                        "[ ]"
+                    };
+                    format!("{mark} {}", choice.label)
+                })
+                .collect();
+            let mut lines = vec![
+                format!("{DONE} ({} selected)", marked.len()),
+                SELECT_ALL.to_string(),
+                SELECT_NONE.to_string(),
+            ];
+            lines.extend(entries.iter().cloned());
+
+            let Some(picked) = self.run(menu.title, &lines)? else {
+                return Err(Cancelled::selection().into());
+            };
+            let indices = picked
+                .iter()
+                .filter_map(|line| entries.iter().position(|entry| entry.trim_end() == line));
+            if picked.len() > 1 {
+                return Ok(menu.ids(indices.collect()));
+            }
+            match picked[0].as_str() {
+                line if line.starts_with(DONE) => return Ok(menu.ids(marked)),
+                SELECT_ALL => marked = (0..menu.choices.len()).collect(),
+                SELECT_NONE => marked.clear(),
+                // Typed text that matches no entry just shows the list again.
+                _ => toggle(&mut marked, indices),
+            }
+        }
+    }
+
+    fn single_select(&self, menu: &Menu) -> Result<String> {
+        if menu.choices.is_empty() {
+            bail!("No options available");
+        }
+        let lines: Vec<String> = menu
+            .choices
+            .iter()
+            .map(|choice| choice.label.clone())
+            .collect();
+        let Some(picked) = self.run(menu.title, &lines)? else {
+            return Err(Cancelled::selection().into());
+        };
+        let index = lines
+            .iter()
+            .position(|line| line.trim_end() == picked[0])
+            .ok_or_else(|| anyhow!("Unknown selection '{}'", picked[0]))?;
+        Ok(menu.choices[index].id.clone())
+    }
+
+    fn confirm(&self, question: &str) -> Result<bool> {
+        let lines = ["Yes".to_string(), "No".to_string()];
+        Ok(self
+            .run(question, &lines)?
+            .is_some_and(|picked| picked[0] == "Yes"))
+    }
 }
@@ -0,0 +1,174 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::fs_ops::{self, Fingerprint};
+
+const STATE_FILE: &str = "state.json";
+const SCAN_FILE: &str = "scan.json";
+/// Seconds `waybar` reuses its last scan of the tracked paths before walking them again.
+pub const SCAN_INTERVAL: u64 = 60;
+
+/// Summary of the last backup and restore, cached so status displays never touch the remote.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SyncState {
+    /// Unix time of the last successful backup.
+    #[serde(default)]
+    pub last_backup: Option<u64>,
+    /// Unix time of the last successful restore.
+    #[serde(default)]
+    pub last_restore: Option<u64>,
+    /// Commit recorded by the last backup (git storage only).
+    #[serde(default)]
+    pub commit: Option<String>,
+    /// Snapshots waiting to be pushed.
+    #[serde(default)]
+    pub pending: usize,
+    /// Error from the last failed backup or restore, cleared by the next success.
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Fingerprints of tracked paths as of the last backup or restore.
+    #[serde(default)]
+    pub paths: BTreeMap<String, Fingerprint>,
+    /// Paths picked in the last interactive selection, pre-selected the next time.
+    #[serde(default)]
+    pub last_selection: LastSelection,
+}
+
+/// Last paths chosen in the `backup` and `restore` selectors.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct LastSelection {
+    #[serde(default)]
+    pub backup: Vec<String>,
+    #[serde(default)]
+    pub restore: Vec<String>,
+}
+
+impl SyncState {
+    /// Tracked paths whose files changed since they were last backed up or restored.
+    pub fn modified_paths(&self, paths: &[String]) -> Result<Vec<String>> {
+        let mut modified = Vec::new();
+        for path in paths {
+            let current = fs_ops::fingerprint(path)?;
+            if current != self.paths.get(path).copied() {
+                modified.push(path.clone());
+            }
+        }
+        Ok(modified)
+    }
+
+    /// `modified_paths`, reusing the result of a scan made within the last `SCAN_INTERVAL`
+    /// seconds while no tracked path itself changed. Fingerprinting stats every file under
+    /// every tracked tree, which is too much to repeat on each status-bar poll; the cheap
+    /// check still catches edited top-level files and files added to or removed from (or
+    /// replaced by an editor's save in) a tracked directory, but edits deeper down can take
+    /// up to `SCAN_INTERVAL` seconds to show.
+    pub fn modified_paths_throttled(&self, paths: &[String]) -> Result<Vec<String>> {
+        let now = now();
+        let synced = self.last_backup.max(self.last_restore).unwrap_or(0);
+        let mut roots = Vec::new();
+        for path in paths {
+            roots.push((path.clone(), fs_ops::modified_time(path)?));
+        }
+        if let Some(scan) = load_scan()
+            && scan.roots == roots
+            && scan.checked_at > synced
+            && now.saturating_sub(scan.checked_at) < SCAN_INTERVAL
+        {
+            return Ok(scan.modified);
+        }
+        let modified = self.modified_paths(paths)?;
+        // The cache only saves work; failing to write it is not worth reporting.
+        let _ = save_scan(&Scan {
+            checked_at: now,
+            roots,
+            modified: modified.clone(),
+        });
+        Ok(modified)
+    }
+
+    /// Remember how `paths` look right now, after they were synced.
+    pub fn record_paths(&mut self, paths: &[String]) -> Result<()> {
+        for path in paths {
+            match fs_ops::fingerprint(path)? {
+                Some(print) => {
+                    self.paths.insert(path.clone(), print);
+                }
+                None => {
+                    self.paths.remove(path);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn state_path() -> Result<PathBuf> {
+    Ok(config::data_dir()?.join(STATE_FILE))
+}
+
+/// Load the cached state, starting fresh if none was written yet.
+pub fn load() -> Result<SyncState> {
+    let path = state_path()?;
+    match fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data)
+            .with_context(|| format!("Failed parsing sync state {}", path.display())),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(SyncState::default()),
+        Err(err) => Err(err).with_context(|| format!("Failed reading {}", path.display())),
+    }
+}
+
+pub fn save(state: &SyncState) -> Result<()> {
+    let path = state_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(state)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+/// Load, modify and save the cached state in one go.
+pub fn update(apply: impl FnOnce(&mut SyncState) -> Result<()>) -> Result<()> {
+    let mut state = load().unwrap_or_default();
+    apply(&mut state)?;
+    save(&state)
+}
+
+/// Result of the last `modified_paths_throttled` walk.
+#[derive(Debug, Serialize, Deserialize)]
+struct Scan {
+    checked_at: u64,
+    /// Each tracked path with its own modification time at the time of the scan.
+    roots: Vec<(String, Option<u64>)>,
+    modified: Vec<String>,
+}
+
+fn load_scan() -> Option<Scan> {
+    let data = fs::read_to_string(config::data_dir().ok()?.join(SCAN_FILE)).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn save_scan(scan: &Scan) -> Result<()> {
+    let path = config::data_dir()?.join(SCAN_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed creating {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_vec(scan)?)
+        .with_context(|| format!("Failed writing {}", path.display()))
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
@@ -0,0 +1,516 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use fs_extra::dir::{self, CopyOptions};
+use serde::{Deserialize, Serialize};
+use tempfile::{TempDir, tempdir};
+
+use crate::config::{self, SyncConfig};
+use crate::fs_ops;
+use crate::git;
+use crate::say;
+
+/// Marker written into directory storage so we never clear a directory we did not create.
+const DIRECTORY_MARKER: &str = ".omarchy-syncd-storage";
+const TARBALL_PREFIX: &str = "omarchy-syncd-";
+const TARBALL_SUFFIX: &str = ".tar.gz";
+
+/// Where snapshots are kept, selected via `storage.kind`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// Commit and push to the git remote configured under [repo].
+    #[default]
+    Git,
+    /// Mirror snapshots into a plain directory (e.g. a mounted drive).
+    Directory,
+    /// Write rotating compressed tarballs into a directory.
+    Tarball,
+}
+
+impl StorageKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StorageKind::Git => "git",
+            StorageKind::Directory => "directory",
+            StorageKind::Tarball => "tarball",
+        }
+    }
+}
+
+/// A checked-out snapshot that backup writes into and restore reads from.
+pub struct Workspace {
+    path: PathBuf,
+    _temp: Option<TempDir>,
+}
+
+impl Workspace {
+    fn temporary() -> Result<Self> {
+        let temp = tempdir().context("Failed to create temporary directory")?;
+        let path = temp.path().join("repo");
+        fs::create_dir_all(&path).with_context(|| format!("Failed creating {}", path.display()))?;
+        Ok(Self {
+            path,
+            _temp: Some(temp),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// What [`Storage::publish`] did with a snapshot.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Published {
+    /// Whether a new snapshot was recorded; false when nothing changed.
+    pub changed: bool,
+    /// Commit at the tip of the backup branch (git storage only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Snapshots sent to the remote during this run.
+    pub pushed: usize,
+    /// Snapshots left queued because the remote was unreachable.
+    pub queued: usize,
+}
+
+/// Backing store for snapshots. Backup and restore only ever talk to this trait.
+pub trait Storage {
+    /// Materialize the latest snapshot, leaving the workspace empty when none exists.
+    fn checkout(&self) -> Result<Workspace>;
+    /// Save the workspace contents as the newest snapshot.
+    fn publish(&self, workspace: &Workspace, message: &str) -> Result<Published>;
+    /// Send snapshots queued while the remote was unreachable; returns how many were sent.
+    fn flush(&self) -> Result<usize> {
+        Ok(0)
+    }
+    /// Number of snapshots waiting to be sent to the remote.
+    fn pending(&self) -> Result<usize> {
+        Ok(0)
+    }
+    /// Files edited through linked paths since the last snapshot, relative to the snapshot
+    /// root. Only git storage keeps a local copy that links can point into.
+    fn local_edits(&self) -> Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+    /// Directory holding the last synced snapshot, readable without fetching anything;
+    /// `None` when the storage keeps no such copy.
+    fn local_copy(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Persistent clone used by git storage so snapshots survive being offline.
+pub fn local_repo_dir() -> Result<PathBuf> {
+    Ok(config::data_dir()?.join("repo"))
+}
+
+pub fn open(cfg: &SyncConfig) -> Result<Box<dyn Storage>> {
+    match cfg.storage.kind {
+        StorageKind::Git => {
+            if cfg.repo.url.trim().is_empty() {
+                anyhow::bail!(
+                    "No repository URL configured. Set `url` under [repo] or choose another storage kind."
+                );
+            }
+            Ok(Box::new(GitStorage {
+                url: cfg.repo.url.clone(),
+                branch: cfg.repo.branch.clone(),
+                local: local_repo_dir()?,
+                keep_local_edits: cfg.files.uses_links(),
+            }))
+        }
+        StorageKind::Directory => Ok(Box::new(DirectoryStorage {
+            root: storage_root(cfg)?,
+        })),
+        StorageKind::Tarball => Ok(Box::new(TarballStorage {
+            root: storage_root(cfg)?,
+            keep: cfg.storage.keep.max(1),
+        })),
+    }
+}
+
+/// Read-only storage backed by a `git bundle` file, e.g. one written by `omarchy-syncd export`.
+pub fn open_bundle(cfg: &SyncConfig, bundle: &Path) -> Result<Box<dyn Storage>> {
+    if !bundle.is_file() {
+        anyhow::bail!("Bundle file {} does not exist", bundle.display());
+    }
+    Ok(Box::new(BundleStorage {
+        bundle: bundle.to_path_buf(),
+        branch: cfg.repo.branch.clone(),
+    }))
+}
+
+fn storage_root(cfg: &SyncConfig) -> Result<PathBuf> {
+    let raw = cfg.storage.path.as_deref().with_context(|| {
+        format!(
+            "Storage kind '{}' requires `path` under [storage]",
+            cfg.storage.kind.as_str()
+        )
+    })?;
+    Ok(PathBuf::from(shellexpand::tilde(raw).into_owned()))
+}
+
+struct GitStorage {
+    url: String,
+    branch: String,
+    local: PathBuf,
+    /// Linked paths edit the clone directly, so its uncommitted edits count as local changes.
+    keep_local_edits: bool,
+}
+
+impl GitStorage {
+    fn has_local(&self) -> bool {
+        self.local.join(".git").is_dir()
+    }
+
+    fn local_matches_config(&self) -> Result<bool> {
+        Ok(self.has_local()
+            && git::remote_url(&self.local)?.as_deref() == Some(self.url.as_str())
+            && git::current_branch(&self.local)?.as_deref() == Some(self.branch.as_str()))
+    }
+
+    /// Bring the clone up to date with the remote, keeping uncommitted edits: linked paths
+    /// write into the clone, and the config may have left link mode since they were made.
+    fn update_local(&self) -> Result<()> {
+        let stashed = git::stash(&self.local)?;
+        let updated = git::update(&self.local, &self.branch);
+        if stashed {
+            git::unstash(&self.local)?;
+        }
+        updated
+    }
+
+    /// Push local commits, recording how many were sent or left queued in `published`.
+    fn push_pending(&self, published: &mut Published) -> Result<()> {
+        let pending = git::pending_commits(&self.local, &self.branch)?;
+        if pending == 0 {
+            return Ok(());
+        }
+        match git::push(&self.local, &self.branch) {
+            Ok(()) => {
+                published.pushed = pending;
+                Ok(())
+            }
+            Err(err) if git::is_unreachable(&err) => {
+                say!(
+                    "Remote unreachable; {pending} snapshot(s) queued. They will be pushed by the next backup or `omarchy-syncd push`."
+                );
+                published.queued = pending;
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl Storage for GitStorage {
+    fn checkout(&self) -> Result<Workspace> {
+        if self.local_matches_config()? {
+            match self.update_local() {
+                Ok(()) => {}
+                Err(err) if git::is_unreachable(&err) => say!(
+                    "Remote unreachable; using the local copy from the last sync at {}.",
+                    self.local.display()
+                ),
+                Err(err) => return Err(err),
+            }
+        } else {
+            if self.has_local() {
+                let branch =
+                    git::current_branch(&self.local)?.unwrap_or_else(|| self.branch.clone());
+                let pending = git::pending_commits(&self.local, &branch)?;
+                if pending > 0 {
+                    anyhow::bail!(
+                        "{} holds {pending} unpushed snapshot(s) for a different remote or branch. Push them with `git -C {} push` or delete the directory.",
+                        self.local.display(),
+                        self.local.display()
+                    );
+                }
+                // Linked paths point into the clone, so its uncommitted edits are the only copy.
+                let edits = git::local_edits(&self.local)?;
+                if !edits.is_empty() {
+                    anyhow::bail!(
+                        "{} holds uncommitted edits for a different remote or branch ({}). Switch [repo] back and run `omarchy-syncd backup` to keep them, or delete {} to discard them.",
+                        self.local.display(),
+                        edits
+                            .iter()
+                            .map(|path| path.display().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        self.local.display()
+                    );
+                }
+            }
+            if let Some(parent) = self.local.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed creating {}", parent.display()))?;
+            }
+            git::clone_repo(&self.url, &self.branch, &self.local)
+                .context("Failed to clone repository")?;
+        }
+
+        Ok(Workspace {
+            path: self.local.clone(),
+            _temp: None,
+        })
+    }
+
+    fn publish(&self, workspace: &Workspace, message: &str) -> Result<Published> {
+        let mut published = Published {
+            changed: git::commit(workspace.path(), message)?,
+            ..Published::default()
+        };
+        if !published.changed {
+            say!("No changes to commit.");
+        }
+        self.push_pending(&mut published)?;
+        published.commit = git::head_commit(&self.local)?;
+        Ok(published)
+    }
+
+    fn flush(&self) -> Result<usize> {
+        let pending = self.pending()?;
+        if pending == 0 {
+            return Ok(0);
+        }
+        self.update_local()?;
+        git::push(&self.local, &self.branch)?;
+        Ok(pending)
+    }
+
+    fn pending(&self) -> Result<usize> {
+        if !self.local_matches_config()? {
+            return Ok(0);
+        }
+        git::pending_commits(&self.local, &self.branch)
+    }
+
+    fn local_copy(&self) -> Option<PathBuf> {
+        self.has_local().then(|| self.local.clone())
+    }
+
+    fn local_edits(&self) -> Result<Vec<PathBuf>> {
+        if !self.keep_local_edits || !self.local_matches_config()? {
+            return Ok(Vec::new());
+        }
+        git::local_edits(&self.local)
+    }
+}
+
+struct BundleStorage {
+    bundle: PathBuf,
+    branch: String,
+}
+
+impl Storage for BundleStorage {
+    fn checkout(&self) -> Result<Workspace> {
+        let workspace = Workspace::temporary()?;
+        git::clone_repo(
+            &self.bundle.to_string_lossy(),
+            &self.branch,
+            workspace.path(),
+        )
+        .with_context(|| format!("Failed to read bundle {}", self.bundle.display()))?;
+        Ok(workspace)
+    }
+
+    fn publish(&self, _workspace: &Workspace, _message: &str) -> Result<Published> {
+        anyhow::bail!("Bundle files are read-only; back up to the configured storage instead.")
+    }
+}
+
+struct DirectoryStorage {
+    root: PathBuf,
+}
+
+impl Storage for DirectoryStorage {
+    fn checkout(&self) -> Result<Workspace> {
+        ensure_storage_dir(&self.root)?;
+        let workspace = Workspace::temporary()?;
+        copy_contents(&self.root, workspace.path())?;
+        let marker = workspace.path().join(DIRECTORY_MARKER);
+        if marker.exists() {
+            fs::remove_file(&marker)
+                .with_context(|| format!("Failed removing {}", marker.display()))?;
+        }
+        Ok(workspace)
+    }
+
+    fn publish(&self, workspace: &Workspace, _message: &str) -> Result<Published> {
+        ensure_storage_dir(&self.root)?;
+        let workdir = workspace.path();
+        if fs_ops::trees_equal(workdir, &self.root, &[DIRECTORY_MARKER])? {
+            say!("No changes to save.");
+            return Ok(Published::default());
+        }
+
+        let marker = self.root.join(DIRECTORY_MARKER);
+        let is_empty = self
+            .root
+            .read_dir()
+            .with_context(|| format!("Failed reading {}", self.root.display()))?
+            .next()
+            .is_none();
+        if !is_empty && !marker.exists() {
+            anyhow::bail!(
+                "Refusing to overwrite {} because it was not created by omarchy-syncd. Point `storage.path` at an empty directory.",
+                self.root.display()
+            );
+        }
+
+        for entry in self
+            .root
+            .read_dir()
+            .with_context(|| format!("Failed reading {}", self.root.display()))?
+        {
+            let path = entry?.path();
+            if path == marker {
+                continue;
+            }
+            if path.is_dir() && !path.is_symlink() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            }
+            .with_context(|| format!("Failed removing stale {}", path.display()))?;
+        }
+
+        copy_contents(workdir, &self.root)?;
+        fs::write(&marker, b"").with_context(|| format!("Failed writing {}", marker.display()))?;
+        say!("Saved snapshot to {}", self.root.display());
+        Ok(Published {
+            changed: true,
+            ..Published::default()
+        })
+    }
+
+    fn local_copy(&self) -> Option<PathBuf> {
+        self.root.is_dir().then(|| self.root.clone())
+    }
+}
+
+struct TarballStorage {
+    root: PathBuf,
+    keep: usize,
+}
+
+impl TarballStorage {
+    fn archives(&self) -> Result<Vec<PathBuf>> {
+        let mut archives: Vec<PathBuf> = self
+            .root
+            .read_dir()
+            .with_context(|| format!("Failed reading {}", self.root.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(TARBALL_PREFIX) && name.ends_with(TARBALL_SUFFIX)
+                    })
+            })
+            .collect();
+        // Names embed a zero-padded timestamp, so lexical order is chronological.
+        archives.sort();
+        Ok(archives)
+    }
+
+    fn next_archive_path(&self) -> Result<PathBuf> {
+        let mut millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("System clock is before the Unix epoch")?
+            .as_millis();
+        loop {
+            let candidate = self
+                .root
+                .join(format!("{TARBALL_PREFIX}{millis:015}{TARBALL_SUFFIX}"));
+            if !candidate.exists() {
+                return Ok(candidate);
+            }
+            millis += 1;
+        }
+    }
+}
+
+impl Storage for TarballStorage {
+    fn checkout(&self) -> Result<Workspace> {
+        ensure_storage_dir(&self.root)?;
+        let workspace = Workspace::temporary()?;
+        if let Some(latest) = self.archives()?.pop() {
+            extract_archive(&latest, workspace.path())?;
+        }
+        Ok(workspace)
+    }
+
+    fn publish(&self, workspace: &Workspace, _message: &str) -> Result<Published> {
+        ensure_storage_dir(&self.root)?;
+        let workdir = workspace.path();
+        let mut archives = self.archives()?;
+        if let Some(latest) = archives.last() {
+            let previous = tempdir().context("Failed to create temporary directory")?;
+            extract_archive(latest, previous.path())?;
+            if fs_ops::trees_equal(workdir, previous.path(), &[])? {
+                say!("No changes to save.");
+                return Ok(Published::default());
+            }
+        }
+
+        let archive_path = self.next_archive_path()?;
+        let file = File::create(&archive_path)
+            .with_context(|| format!("Failed creating {}", archive_path.display()))?;
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        builder.follow_symlinks(false);
+        builder
+            .append_dir_all(".", workdir)
+            .with_context(|| format!("Failed archiving {}", workdir.display()))?;
+        builder
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .with_context(|| format!("Failed finishing {}", archive_path.display()))?;
+        say!("Saved snapshot to {}", archive_path.display());
+
+        archives.push(archive_path);
+        let excess = archives.len().saturating_sub(self.keep);
+        for old in archives.drain(..excess) {
+            fs::remove_file(&old)
+                .with_context(|| format!("Failed removing old snapshot {}", old.display()))?;
+        }
+        Ok(Published {
+            changed: true,
+            ..Published::default()
+        })
+    }
+}
+
+fn ensure_storage_dir(root: &Path) -> Result<()> {
+    if !root.is_dir() {
+        anyhow::bail!(
+            "Storage directory {} does not exist. Create it (or mount the drive) first.",
+            root.display()
+        );
+    }
+    Ok(())
+}
+
+fn copy_contents(from: &Path, to: &Path) -> Result<()> {
+    let mut options = CopyOptions::new();
+    options.content_only = true;
+    options.overwrite = true;
+    dir::copy(from, to, &options)
+        .with_context(|| format!("Failed copying {} to {}", from.display(), to.display()))?;
+    Ok(())
+}
+
+fn extract_archive(archive: &Path, into: &Path) -> Result<()> {
+    let file =
+        File::open(archive).with_context(|| format!("Failed opening {}", archive.display()))?;
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(into)
+        .with_context(|| format!("Failed extracting {}", archive.display()))
+}
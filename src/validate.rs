@@ -0,0 +1,380 @@
+use std::{fmt, ops::Range, path::Path};
+
+use serde::Serialize;
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use toml_edit::{ImDocument, Item, Key, Table, TableLike};
+
+use crate::bundles;
+use crate::config::{
+    CONFIG_VERSION, FileConfig, HooksConfig, NotificationsConfig, PackagesConfig, RepoConfig,
+    StorageConfig, SyncConfig, UiConfig,
+};
+use crate::fs_ops;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A single finding, located by 1-based line and column.
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}: {}",
+            self.line,
+            self.column,
+            self.severity.as_str(),
+            self.message
+        )
+    }
+}
+
+/// Check a config file's contents and return every problem found, in file order.
+pub fn check(raw: &str) -> Vec<Problem> {
+    let mut report = Report {
+        raw,
+        problems: Vec::new(),
+    };
+    let doc = match ImDocument::parse(raw) {
+        Ok(doc) => doc,
+        Err(err) => {
+            report.error(err.span(), err.message().trim_end());
+            return report.problems;
+        }
+    };
+
+    let root = doc.as_table();
+    // Unknown entries are blanked out before deserializing so serde can still report type
+    // errors that would otherwise hide behind its first unknown-field error.
+    let mut unknown_spans = Vec::new();
+    for (name, item) in root.iter() {
+        let Some((key, _)) = root.get_key_value(name) else {
+            continue;
+        };
+        if name == "version" {
+            if let Some(version) = item.as_integer()
+                && version > i64::from(CONFIG_VERSION)
+            {
+                report.error(
+                    item.span(),
+                    format!(
+                        "version {version} is newer than this omarchy-syncd supports (version {CONFIG_VERSION})"
+                    ),
+                );
+            }
+            continue;
+        }
+        let Some(known) = section_keys(name) else {
+            report.error(
+                key.span(),
+                unknown(
+                    &if item.is_table_like() {
+                        format!("section `{name}`")
+                    } else {
+                        format!("key `{name}`")
+                    },
+                    name,
+                    section_names(),
+                ),
+            );
+            entry_spans(key, item, &mut unknown_spans);
+            continue;
+        };
+        let Some(table) = item.as_table_like() else {
+            continue;
+        };
+        for (field, value) in table.iter() {
+            if known.contains(&field) {
+                continue;
+            }
+            let Some((field_key, _)) = table.get_key_value(field) else {
+                continue;
+            };
+            report.error(
+                field_key.span(),
+                unknown(
+                    &format!("key `{field}` in [{name}]"),
+                    field,
+                    known.iter().copied(),
+                ),
+            );
+            entry_spans(field_key, value, &mut unknown_spans);
+        }
+    }
+
+    if let Some(files) = root.get("files").and_then(Item::as_table_like) {
+        check_files(&mut report, files);
+        check_link_storage(&mut report, files, root.get("storage"));
+    }
+
+    let known_only = blank(raw, &unknown_spans);
+    if ImDocument::parse(known_only.as_str()).is_ok()
+        && let Err(err) = toml::from_str::<SyncConfig>(&known_only)
+    {
+        report.error(err.span(), err.message().trim_end());
+    }
+
+    report
+        .problems
+        .sort_by_key(|problem| (problem.line, problem.column));
+    report.problems
+}
+
+/// Keys accepted in `section`, or `None` if there is no such section. They are read from the
+/// config structs, so they always match what `deny_unknown_fields` accepts.
+pub fn section_keys(section: &str) -> Option<&'static [&'static str]> {
+    let keys = match section {
+        "repo" => struct_fields::<RepoConfig>(),
+        "files" => struct_fields::<FileConfig>(),
+        "storage" => struct_fields::<StorageConfig>(),
+        "packages" => struct_fields::<PackagesConfig>(),
+        "hooks" => struct_fields::<HooksConfig>(),
+        "notifications" => struct_fields::<NotificationsConfig>(),
+        "ui" => struct_fields::<UiConfig>(),
+        _ => return None,
+    };
+    Some(keys)
+}
+
+/// Tables accepted at the top level of the config.
+fn section_names() -> impl Iterator<Item = &'static str> {
+    struct_fields::<SyncConfig>()
+        .iter()
+        .copied()
+        .filter(|name| *name != "version")
+}
+
+/// Every field a derived `Deserialize` struct accepts, including optional and skipped ones.
+fn struct_fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    // The deserializer records the field list and then fails; only the list is wanted.
+    let _ = T::deserialize(FieldRecorder(&mut fields));
+    fields
+}
+
+/// Deserializer that captures the field names a struct asks for instead of reading data.
+struct FieldRecorder<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for FieldRecorder<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("only structs have fields"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("fields recorded"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+pub fn has_errors(problems: &[Problem]) -> bool {
+    problems
+        .iter()
+        .any(|problem| problem.severity == Severity::Error)
+}
+
+fn check_files(report: &mut Report, files: &dyn TableLike) {
+    let mut bundle_ids: Vec<&str> = Vec::new();
+    for (id, span) in strings(files.get("bundles")) {
+        if bundle_ids.contains(&id) {
+            report.warning(span, format!("bundle `{id}` is listed more than once"));
+        } else if bundles::find(id).is_none() {
+            report.error(
+                span,
+                unknown(
+                    &format!("bundle `{id}`"),
+                    id,
+                    bundles::all().iter().map(|bundle| bundle.id),
+                ),
+            );
+        }
+        bundle_ids.push(id);
+    }
+
+    let mut seen: Vec<&str> = Vec::new();
+    for (path, span) in strings(files.get("paths")) {
+        if seen.contains(&path) {
+            report.warning(span, format!("`{path}` is listed more than once"));
+            continue;
+        }
+        seen.push(path);
+
+        if let Err(err) = fs_ops::resolve_tracked_path(path) {
+            report.error(span, err.to_string());
+            continue;
+        }
+        let covering = bundle_ids.iter().find_map(|id| {
+            let bundle = bundles::find(id)?;
+            bundle
+                .paths
+                .iter()
+                .find(|covered| Path::new(path).starts_with(covered))
+                .map(|covered| (bundle.id, *covered))
+        });
+        if let Some((id, covered)) = covering {
+            report.warning(
+                span,
+                format!("`{path}` is already tracked by bundle `{id}` (via `{covered}`)"),
+            );
+        }
+    }
+}
+
+/// Linked paths point into the persistent git clone, which other storage kinds do not keep.
+fn check_link_storage(report: &mut Report, files: &dyn TableLike, storage: Option<&Item>) {
+    let kind = storage
+        .and_then(|storage| storage.get("kind"))
+        .and_then(Item::as_str)
+        .unwrap_or("git");
+    if kind == "git" {
+        return;
+    }
+    let global = files.get("mode").into_iter();
+    let per_path = files
+        .get("modes")
+        .and_then(Item::as_table_like)
+        .into_iter()
+        .flat_map(|modes| modes.iter().map(|(_, item)| item));
+    for item in global.chain(per_path) {
+        if item.as_str() == Some("link") {
+            report.error(
+                item.span(),
+                format!("mode = \"link\" needs git storage, but storage kind is '{kind}'"),
+            );
+        }
+    }
+}
+
+/// String entries of an array, with their spans; anything else is left to serde to report.
+fn strings(item: Option<&Item>) -> Vec<(&str, Option<Range<usize>>)> {
+    item.and_then(Item::as_array)
+        .map(|array| {
+            array
+                .iter()
+                .filter_map(|value| Some((value.as_str()?, value.span())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// "unknown {what}", with the closest candidate to `name` suggested when one is near.
+fn unknown<'a>(what: &str, name: &str, candidates: impl Iterator<Item = &'a str>) -> String {
+    let suggestion = candidates
+        .map(|candidate| (strsim::levenshtein(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= 2.max(candidate.len() / 3))
+        .min_by_key(|(distance, _)| *distance);
+    match suggestion {
+        Some((_, candidate)) => format!("unknown {what}; did you mean `{candidate}`?"),
+        None => format!("unknown {what}"),
+    }
+}
+
+/// Byte ranges covering an entry: `key = value` for values, headers and children for tables.
+fn entry_spans(key: &Key, item: &Item, spans: &mut Vec<Range<usize>>) {
+    match item {
+        Item::Value(value) => {
+            if let (Some(key), Some(value)) = (key.span(), value.span()) {
+                spans.push(key.start..value.end);
+            }
+        }
+        Item::Table(table) => table_spans(table, spans),
+        Item::ArrayOfTables(tables) => {
+            for table in tables.iter() {
+                table_spans(table, spans);
+            }
+        }
+        Item::None => {}
+    }
+}
+
+fn table_spans(table: &Table, spans: &mut Vec<Range<usize>>) {
+    spans.extend(table.span());
+    for (name, _) in table.iter() {
+        if let Some((key, item)) = table.get_key_value(name) {
+            entry_spans(key, item, spans);
+        }
+    }
+}
+
+/// Replace everything inside `spans` with spaces, keeping line breaks so offsets still match.
+fn blank(raw: &str, spans: &[Range<usize>]) -> String {
+    let mut bytes = raw.as_bytes().to_vec();
+    for span in spans {
+        for byte in &mut bytes[span.start.min(raw.len())..span.end.min(raw.len())] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    }
+    String::from_utf8(bytes).unwrap_or_else(|_| raw.to_string())
+}
+
+struct Report<'a> {
+    raw: &'a str,
+    problems: Vec<Problem>,
+}
+
+impl Report<'_> {
+    fn error(&mut self, span: Option<Range<usize>>, message: impl Into<String>) {
+        self.push(Severity::Error, span, message.into());
+    }
+
+    fn warning(&mut self, span: Option<Range<usize>>, message: impl Into<String>) {
+        self.push(Severity::Warning, span, message.into());
+    }
+
+    fn push(&mut self, severity: Severity, span: Option<Range<usize>>, message: String) {
+        let (line, column) = span.map_or((1, 1), |span| position(self.raw, span.start));
+        self.problems.push(Problem {
+            severity,
+            line,
+            column,
+            message,
+        });
+    }
+}
+
+/// 1-based line and column of a byte offset.
+fn position(raw: &str, offset: usize) -> (usize, usize) {
+    let before = &raw[..offset.min(raw.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rfind('\n')
+        .map_or(before, |newline| &before[newline + 1..])
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
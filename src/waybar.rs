@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+use crate::state::SyncState;
+
+/// Output of a Waybar `custom` module with `return-type` set to `json`.
+#[derive(Debug, Serialize)]
+pub struct Module {
+    pub text: String,
+    pub tooltip: String,
+    pub class: &'static str,
+}
+
+/// Build the module from the cached state and the tracked paths modified since the last sync.
+pub fn render(state: &SyncState, modified: &[String], now: u64) -> Module {
+    let Some(last_backup) = state.last_backup else {
+        let mut tooltip = vec!["No backup recorded yet.".to_string()];
+        if let Some(error) = &state.last_error {
+            tooltip.push(format!("Last error: {error}"));
+        }
+        return Module {
+            text: "never".to_string(),
+            tooltip: tooltip.join("\n"),
+            class: if state.last_error.is_some() {
+                "error"
+            } else {
+                "never"
+            },
+        };
+    };
+
+    let mut text = format_age(now.saturating_sub(last_backup));
+    let mut tooltip = vec![match &state.commit {
+        Some(commit) => format!(
+            "Last backup: {text} ago ({})",
+            &commit[..commit.len().min(7)]
+        ),
+        None => format!("Last backup: {text} ago"),
+    }];
+    if let Some(last_restore) = state.last_restore {
+        tooltip.push(format!(
+            "Last restore: {} ago",
+            format_age(now.saturating_sub(last_restore))
+        ));
+    }
+
+    if state.pending > 0 {
+        text.push_str(&format!(" ↑{}", state.pending));
+        tooltip.push(format!("Pending pushes: {}", state.pending));
+    }
+    if modified.is_empty() {
+        tooltip.push("Dotfiles match the last sync.".to_string());
+    } else {
+        text.push_str(" *");
+        tooltip.push(format!("Modified since last sync: {}", modified.join(", ")));
+    }
+    if let Some(error) = &state.last_error {
+        tooltip.push(format!("Last error: {error}"));
+    }
+
+    let class = if state.last_error.is_some() {
+        "error"
+    } else if !modified.is_empty() {
+        "modified"
+    } else if state.pending > 0 {
+        "pending"
+    } else {
+        "synced"
+    };
+
+    Module {
+        text,
+        tooltip: tooltip.join("\n"),
+        class,
+    }
+}
+
+/// Render an age in seconds as `<1m`, `5m`, `3h` or `2d`.
+pub fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => "<1m".to_string(),
+        60..3_600 => format!("{}m", secs / 60),
+        3_600..86_400 => format!("{}h", secs / 3_600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
@@ -6,7 +6,6 @@ use std::process::Command as StdCommand;
 use anyhow::{Context, Result};
 use assert_cmd::Command;
 use serde::Deserialize;
-use serde_json;
 use tempfile::tempdir;
 use walkdir::WalkDir;
 
@@ -73,7 +72,7 @@ fn init_empty_remote(base: &Path, name: &str) -> Result<PathBuf> {
     let remote = base.join(name);
     run_git(Some(base), &["init", "--bare", path_str(&remote)?])?;
     StdCommand::new("git")
-        .args(&[
+        .args([
             "--git-dir",
             path_str(&remote)?,
             "symbolic-ref",
@@ -174,7 +173,7 @@ fn init_with_defaults_and_extra_path_writes_config() -> Result<()> {
         let bundle = omarchy_syncd::bundles::find(bundle_id).expect("bundle should exist");
         for path in bundle.paths {
             assert!(
-                resolved_set.contains(&path.to_string()),
+                resolved_set.contains(*path),
                 "missing {path} from resolved defaults"
             );
         }
@@ -359,7 +358,7 @@ fn init_accepts_bundle_flags() -> Result<()> {
     let bundle = omarchy_syncd::bundles::find("core_desktop").expect("core bundle exists");
     for path in bundle.paths {
         assert!(
-            resolved_set.contains(&path.to_string()),
+            resolved_set.contains(*path),
             "resolved bundle should include {path}"
         );
     }
@@ -386,6 +385,9 @@ fn install_command_updates_config_via_cli() -> Result<()> {
         ])
         .assert()
         .success();
+    let config_path = find_config_file(&home)?;
+    let written = fs::read_to_string(&config_path)?;
+    fs::write(&config_path, format!("# synced from the laptop\n{written}"))?;
 
     base_command(&home)
         .args([
@@ -401,8 +403,11 @@ fn install_command_updates_config_via_cli() -> Result<()> {
         .assert()
         .success();
 
-    let config_path = find_config_file(&home)?;
     let raw = fs::read_to_string(&config_path)?;
+    assert!(
+        raw.starts_with("# synced from the laptop\n"),
+        "install should keep comments: {raw}"
+    );
     let cfg: omarchy_syncd::config::SyncConfig = toml::from_str(&raw)?;
 
     assert_eq!(
@@ -421,12 +426,12 @@ fn install_command_updates_config_via_cli() -> Result<()> {
     let resolved = cfg.resolved_paths()?;
     let resolved_set: HashSet<_> = resolved.into_iter().collect();
     for path in omarchy_syncd::bundles::find("terminals").unwrap().paths {
-        assert!(resolved_set.contains(&path.to_string()));
+        assert!(resolved_set.contains(*path));
     }
     for path in omarchy_syncd::bundles::find("dev_git").unwrap().paths {
-        assert!(resolved_set.contains(&path.to_string()));
+        assert!(resolved_set.contains(*path));
     }
-    assert!(resolved_set.contains(&"~/.config/custom-app".to_string()));
+    assert!(resolved_set.contains("~/.config/custom-app"));
 
     Ok(())
 }
@@ -610,3 +615,1766 @@ fn backup_initializes_empty_remote() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn backup_reports_missing_repository_with_hint() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+
+    let missing_remote = temp.path().join("missing-remote.git");
+
+    base_command(&home)
+        .args([
+            "config",
+            "--write",
+            "--repo-url",
+            path_str(&missing_remote)?,
+            "--path",
+            "~/.config/hypr",
+        ])
+        .assert()
+        .success();
+
+    let output = base_command(&home)
+        .args(["backup", "--no-ui"])
+        .output()
+        .context("failed to run backup against a missing remote")?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(
+        stderr.contains("the repository could not be found"),
+        "missing remote should be classified: {stderr}"
+    );
+    assert!(
+        stderr.contains("hint: Check `repo.url`"),
+        "missing remote should come with a hint: {stderr}"
+    );
+    assert!(
+        !stderr.contains("fatal:"),
+        "raw git output should be hidden without --verbose: {stderr}"
+    );
+
+    let verbose = base_command(&home)
+        .args(["--verbose", "backup", "--no-ui"])
+        .output()
+        .context("failed to run verbose backup against a missing remote")?;
+    assert!(!verbose.status.success());
+    let verbose_stderr = String::from_utf8(verbose.stderr)?;
+    assert!(
+        verbose_stderr.contains("fatal:"),
+        "--verbose should surface raw git output: {verbose_stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn directory_storage_roundtrip() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+    let storage_dir = temp.path().join("usb-drive");
+    fs::create_dir_all(&storage_dir)?;
+
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+    fs::write(&hypr_conf, "monitor = DP-1\n")?;
+
+    base_command(&home)
+        .args([
+            "config",
+            "--write",
+            "--storage",
+            "directory",
+            "--storage-path",
+            path_str(&storage_dir)?,
+            "--path",
+            "~/.config/hypr",
+        ])
+        .assert()
+        .success();
+
+    base_command(&home)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(storage_dir.join(".config/hypr/hyprland.conf"))?,
+        "monitor = DP-1\n"
+    );
+
+    fs::remove_dir_all(hypr_conf.parent().unwrap())?;
+
+    base_command(&home)
+        .args(["restore", "--no-ui"])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&hypr_conf)?, "monitor = DP-1\n");
+
+    Ok(())
+}
+
+#[test]
+fn tarball_storage_rotates_snapshots() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+    let storage_dir = temp.path().join("archives");
+    fs::create_dir_all(&storage_dir)?;
+
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            "[files]\npaths = [\"~/.config/hypr\"]\n\n[storage]\nkind = \"tarball\"\npath = \"{}\"\nkeep = 2\n",
+            path_str(&storage_dir)?
+        ),
+    )?;
+
+    for revision in 1..=3 {
+        fs::write(&hypr_conf, format!("# revision {revision}\n"))?;
+        base_command(&home)
+            .args(["backup", "--no-ui"])
+            .assert()
+            .success();
+    }
+
+    let unchanged = base_command(&home)
+        .args(["backup", "--no-ui"])
+        .output()
+        .context("failed to run unchanged tarball backup")?;
+    assert!(unchanged.status.success());
+    assert!(String::from_utf8(unchanged.stdout)?.contains("No changes to save."));
+
+    let archives = fs::read_dir(&storage_dir)?.count();
+    assert_eq!(archives, 2, "only the newest snapshots should be kept");
+
+    fs::remove_dir_all(hypr_conf.parent().unwrap())?;
+    base_command(&home)
+        .args(["restore", "--no-ui"])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&hypr_conf)?, "# revision 3\n");
+
+    Ok(())
+}
+
+/// Point the `syncd-test:` URL prefix at `base` through git's `insteadOf` rewriting.
+fn with_url_rewrite(mut cmd: Command, base: &str) -> Command {
+    cmd.env("GIT_CONFIG_COUNT", "1")
+        .env("GIT_CONFIG_KEY_0", format!("url.{base}.insteadOf"))
+        .env("GIT_CONFIG_VALUE_0", "syncd-test:");
+    cmd
+}
+
+#[test]
+fn offline_backup_is_queued_and_pushed_later() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+
+    let remote = init_remote_repo(temp.path(), "remote-offline.git")?;
+    let online = format!("{}/", path_str(temp.path())?);
+    // Nothing listens on the discard port, so git reports a refused connection.
+    let offline = "http://127.0.0.1:9/";
+
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+    fs::write(&hypr_conf, "monitor = DP-1\n")?;
+
+    base_command(&home)
+        .args([
+            "config",
+            "--write",
+            "--repo-url",
+            "syncd-test:remote-offline.git",
+            "--path",
+            "~/.config/hypr",
+        ])
+        .assert()
+        .success();
+
+    with_url_rewrite(base_command(&home), &online)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+
+    fs::write(&hypr_conf, "monitor = HDMI-A-1\n")?;
+    let queued = with_url_rewrite(base_command(&home), offline)
+        .args(["backup", "--no-ui"])
+        .output()
+        .context("failed to run offline backup")?;
+    assert!(
+        queued.status.success(),
+        "offline backup should succeed: {}",
+        String::from_utf8_lossy(&queued.stderr)
+    );
+    assert!(String::from_utf8(queued.stdout)?.contains("1 snapshot(s) queued"));
+
+    let status = with_url_rewrite(base_command(&home), offline)
+        .arg("status")
+        .output()
+        .context("failed to run status")?;
+    assert!(String::from_utf8(status.stdout)?.contains("Pending snapshots: 1"));
+
+    // Uninstalling would delete the clone holding the queued snapshot.
+    let uninstall = with_url_rewrite(base_command(&home), offline)
+        .args(["uninstall", "--yes"])
+        .output()
+        .context("failed to run uninstall")?;
+    assert!(!uninstall.status.success());
+    assert!(String::from_utf8(uninstall.stderr)?.contains("1 snapshot(s) have not been pushed"));
+    assert!(home.join(".local/share/omarchy-syncd/repo").is_dir());
+
+    with_url_rewrite(base_command(&home), &online)
+        .arg("push")
+        .assert()
+        .success();
+
+    let checkout = temp.path().join("checkout-offline");
+    run_git(
+        Some(temp.path()),
+        &["clone", path_str(&remote)?, path_str(&checkout)?],
+    )?;
+    assert_eq!(
+        fs::read_to_string(checkout.join(".config/hypr/hyprland.conf"))?,
+        "monitor = HDMI-A-1\n",
+        "queued snapshot should reach the remote after push"
+    );
+
+    let status = with_url_rewrite(base_command(&home), &online)
+        .arg("status")
+        .output()
+        .context("failed to run status")?;
+    assert!(String::from_utf8(status.stdout)?.contains("Pending snapshots: 0"));
+
+    Ok(())
+}
+
+#[test]
+fn export_bundle_restores_without_remote() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+
+    let remote = init_remote_repo(temp.path(), "remote-export.git")?;
+
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+    fs::write(&hypr_conf, "monitor = DP-1\n")?;
+
+    base_command(&home)
+        .args([
+            "config",
+            "--write",
+            "--repo-url",
+            path_str(&remote)?,
+            "--path",
+            "~/.config/hypr",
+        ])
+        .assert()
+        .success();
+    base_command(&home)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+
+    let full_bundle = temp.path().join("dotfiles.bundle");
+    let latest_bundle = temp.path().join("latest.bundle");
+    base_command(&home)
+        .args(["export", "--out", path_str(&full_bundle)?])
+        .assert()
+        .success();
+    base_command(&home)
+        .args(["export", "--latest", "--out", path_str(&latest_bundle)?])
+        .assert()
+        .success();
+
+    let history = temp.path().join("history");
+    run_git(
+        Some(temp.path()),
+        &["clone", path_str(&full_bundle)?, path_str(&history)?],
+    )?;
+    let log = StdCommand::new("git")
+        .current_dir(&history)
+        .args(["rev-list", "--count", "HEAD"])
+        .output()?;
+    assert_eq!(String::from_utf8(log.stdout)?.trim(), "2");
+
+    let latest = temp.path().join("latest");
+    run_git(
+        Some(temp.path()),
+        &["clone", path_str(&latest_bundle)?, path_str(&latest)?],
+    )?;
+    let log = StdCommand::new("git")
+        .current_dir(&latest)
+        .args(["rev-list", "--count", "HEAD"])
+        .output()?;
+    assert_eq!(String::from_utf8(log.stdout)?.trim(), "1");
+    assert!(latest.join(".config/hypr/hyprland.conf").exists());
+
+    fs::remove_dir_all(&remote)?;
+    fs::remove_dir_all(hypr_conf.parent().unwrap())?;
+
+    base_command(&home)
+        .args([
+            "restore",
+            "--no-ui",
+            "--from-bundle",
+            path_str(&latest_bundle)?,
+        ])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&hypr_conf)?, "monitor = DP-1\n");
+
+    // A fresh machine without a config restores using the config stored in the bundle.
+    let fresh = temp.path().join("fresh");
+    fs::create_dir_all(&fresh)?;
+    base_command(&fresh)
+        .args([
+            "restore",
+            "--no-ui",
+            "--from-bundle",
+            path_str(&latest_bundle)?,
+        ])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(fresh.join(".config/hypr/hyprland.conf"))?,
+        "monitor = DP-1\n"
+    );
+    assert!(!fresh.join(".config/omarchy-syncd/config.toml").exists());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn write_stub(dir: &Path, name: &str, body: &str) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{body}"))?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn path_with(dir: &Path) -> Result<String> {
+    let current = std::env::var("PATH").unwrap_or_default();
+    Ok(format!("{}:{current}", path_str(dir)?))
+}
+
+#[cfg(unix)]
+#[test]
+fn backup_records_packages_and_install_fills_gaps() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+    let stubs = temp.path().join("stubs");
+    fs::create_dir_all(&stubs)?;
+    let installed = temp.path().join("installed.txt");
+    let log = temp.path().join("install.log");
+
+    // pacman reports whatever is listed in installed.txt; the first line is a foreign package.
+    write_stub(
+        &stubs,
+        "pacman",
+        &format!(
+            "case \"$1\" in\n  -Qqe|-Qq) cat '{installed}' ;;\n  -Qqm) head -n 1 '{installed}' ;;\n  *) echo \"pacman $*\" >> '{log}' ;;\nesac\n",
+            installed = path_str(&installed)?,
+            log = path_str(&log)?
+        ),
+    )?;
+    write_stub(&stubs, "sudo", "exec \"$@\"\n")?;
+    write_stub(
+        &stubs,
+        "paru",
+        &format!("echo \"paru $*\" >> '{}'\n", path_str(&log)?),
+    )?;
+
+    let remote = init_remote_repo(temp.path(), "remote-packages.git")?;
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+    fs::write(&hypr_conf, "monitor = DP-1\n")?;
+
+    base_command(&home)
+        .args([
+            "config",
+            "--write",
+            "--repo-url",
+            path_str(&remote)?,
+            "--path",
+            "~/.config/hypr",
+        ])
+        .assert()
+        .success();
+
+    fs::write(&installed, "yay-bin\nbase\nneovim\n")?;
+    base_command(&home)
+        .env("PATH", path_with(&stubs)?)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+
+    let checkout = temp.path().join("checkout-packages");
+    run_git(
+        Some(temp.path()),
+        &["clone", path_str(&remote)?, path_str(&checkout)?],
+    )?;
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(
+        checkout.join(".config/omarchy-syncd/packages.json"),
+    )?)?;
+    assert_eq!(manifest["native"], serde_json::json!(["base", "neovim"]));
+    assert_eq!(manifest["foreign"], serde_json::json!(["yay-bin"]));
+
+    fs::write(&installed, "base\n")?;
+    let diff = base_command(&home)
+        .env("PATH", path_with(&stubs)?)
+        .args(["packages", "diff"])
+        .output()
+        .context("failed to run packages diff")?;
+    assert!(diff.status.success());
+    let stdout = String::from_utf8(diff.stdout)?;
+    assert!(stdout.contains("  - neovim"), "diff output: {stdout}");
+    assert!(stdout.contains("  - yay-bin"), "diff output: {stdout}");
+    assert!(!stdout.contains("  - base"), "diff output: {stdout}");
+
+    base_command(&home)
+        .env("PATH", path_with(&stubs)?)
+        .args(["packages", "install"])
+        .assert()
+        .success();
+    let calls = fs::read_to_string(&log)?;
+    assert!(
+        calls.contains("pacman -S --needed neovim"),
+        "calls: {calls}"
+    );
+    assert!(calls.contains("paru -S --needed yay-bin"), "calls: {calls}");
+
+    // Scripts cannot answer sudo's password prompt or the package managers' confirmations.
+    write_stub(
+        &stubs,
+        "sudo",
+        &format!("echo \"sudo $*\" >> '{}'\n", path_str(&log)?),
+    )?;
+    fs::write(&log, "")?;
+    base_command(&home)
+        .env("PATH", path_with(&stubs)?)
+        .args(["--non-interactive", "packages", "install"])
+        .assert()
+        .success();
+    let calls = fs::read_to_string(&log)?;
+    assert!(
+        calls.contains("sudo -n pacman -S --needed --noconfirm neovim"),
+        "calls: {calls}"
+    );
+    assert!(
+        calls.contains("paru -S --needed --noconfirm yay-bin"),
+        "calls: {calls}"
+    );
+
+    // Without foreign packages `pacman -Qqm` prints nothing and exits 1.
+    write_stub(
+        &stubs,
+        "pacman",
+        &format!(
+            "case \"$1\" in\n  -Qqe|-Qq) cat '{}' ;;\n  -Qqm) exit 1 ;;\nesac\n",
+            path_str(&installed)?
+        ),
+    )?;
+    fs::write(&hypr_conf, "monitor = DP-2\n")?;
+    base_command(&home)
+        .env("PATH", path_with(&stubs)?)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+    run_git(Some(&checkout), &["pull", "--quiet"])?;
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(
+        checkout.join(".config/omarchy-syncd/packages.json"),
+    )?)?;
+    assert_eq!(manifest["native"], serde_json::json!(["base"]));
+    assert_eq!(manifest["foreign"], serde_json::json!([]));
+
+    Ok(())
+}
+
+#[test]
+fn hooks_receive_context_and_abort_on_failure() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+
+    let remote = init_remote_repo(temp.path(), "remote-hooks.git")?;
+    let hook_log = temp.path().join("hooks.log");
+
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+    fs::write(&hypr_conf, "monitor = DP-1\n")?;
+
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    let write_config = |extra: &str| -> Result<()> {
+        fs::write(
+            config_dir.join("config.toml"),
+            format!(
+                "[repo]\nurl = \"{remote}\"\n\n[files]\npaths = []\nbundles = [\"core_desktop\"]\n\n[hooks]\npost_backup = [\"echo \\\"$OMARCHY_SYNCD_HOOK|$OMARCHY_SYNCD_BUNDLES\\\" >> '{log}'\"]\npost_restore = [\"echo \\\"$OMARCHY_SYNCD_HOOK|$OMARCHY_SYNCD_PATHS\\\" >> '{log}'\"]\n{extra}",
+                remote = path_str(&remote)?,
+                log = path_str(&hook_log)?,
+            ),
+        )?;
+        Ok(())
+    };
+
+    write_config("")?;
+    base_command(&home)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+    base_command(&home)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+    fs::write(&hypr_conf, "monitor = HDMI-A-1\n")?;
+    base_command(&home)
+        .args(["restore", "--no-ui", "--path", "~/.config/hypr"])
+        .assert()
+        .success();
+
+    // Post hooks only hear about what changed: nothing on the repeated backup.
+    let log = fs::read_to_string(&hook_log)?;
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines[0], "post_backup|core_desktop", "hook log: {log}");
+    assert_eq!(lines[1], "post_backup|", "hook log: {log}");
+    assert_eq!(lines[2], "post_restore|~/.config/hypr", "hook log: {log}");
+
+    write_config("pre_backup = [\"exit 3\"]\non_failure = \"abort\"\n")?;
+    let aborted = base_command(&home)
+        .args(["backup", "--no-ui"])
+        .output()
+        .context("failed to run backup with failing hook")?;
+    assert!(!aborted.status.success());
+    assert!(String::from_utf8(aborted.stderr)?.contains("pre_backup hook failed"));
+
+    write_config("pre_backup = [\"sleep 5\"]\ntimeout = 1\non_failure = \"abort\"\n")?;
+    let timed_out = base_command(&home)
+        .args(["backup", "--no-ui"])
+        .output()
+        .context("failed to run backup with slow hook")?;
+    assert!(!timed_out.status.success());
+    assert!(String::from_utf8(timed_out.stderr)?.contains("timed out after 1s"));
+
+    // Hook output must not end up in the JSON document.
+    write_config("pre_backup = [\"echo chatty hook\"]\n")?;
+    let json = base_command(&home)
+        .args(["--output", "json", "backup", "--no-ui"])
+        .output()
+        .context("failed to run backup with chatty hook")?;
+    assert!(json.status.success(), "{json:?}");
+    let report: serde_json::Value = serde_json::from_slice(&json.stdout)?;
+    assert_eq!(report["ok"], true);
+    assert!(String::from_utf8(json.stderr)?.contains("chatty hook"));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn restore_runs_reload_actions_for_changed_bundles_only() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+    let stubs = temp.path().join("stubs");
+    fs::create_dir_all(&stubs)?;
+    let log = temp.path().join("actions.log");
+    write_stub(
+        &stubs,
+        "hyprctl",
+        &format!(
+            "echo \"hyprctl $* $OMARCHY_SYNCD_BUNDLES $OMARCHY_SYNCD_PATHS\" >> '{}'\n",
+            path_str(&log)?
+        ),
+    )?;
+
+    let remote = init_remote_repo(temp.path(), "remote-actions.git")?;
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    let kitty_conf = home.join(".config/kitty/kitty.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+    fs::create_dir_all(kitty_conf.parent().unwrap())?;
+    fs::write(&hypr_conf, "monitor = DP-1\n")?;
+    fs::write(&kitty_conf, "font_size 11\n")?;
+
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            "[repo]\nurl = \"{}\"\n\n[files]\npaths = []\nbundles = [\"core_desktop\", \"terminals\"]\n",
+            path_str(&remote)?
+        ),
+    )?;
+
+    let path = path_with(&stubs)?;
+    base_command(&home)
+        .env("PATH", &path)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+
+    base_command(&home)
+        .env("PATH", &path)
+        .args(["restore", "--no-ui"])
+        .assert()
+        .success();
+    assert!(!log.exists(), "nothing changed, so no action should run");
+
+    fs::write(&kitty_conf, "font_size 14\n")?;
+    base_command(&home)
+        .env("PATH", &path)
+        .args(["restore", "--no-ui"])
+        .assert()
+        .success();
+    assert!(!log.exists(), "terminals has no reload actions");
+    assert_eq!(fs::read_to_string(&kitty_conf)?, "font_size 11\n");
+
+    fs::write(&hypr_conf, "monitor = HDMI-A-1\n")?;
+    let output = base_command(&home)
+        .env("PATH", &path)
+        .args(["restore", "--no-ui"])
+        .output()
+        .context("failed to run restore")?;
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)?.contains("Reloading Core Desktop..."));
+    assert_eq!(
+        fs::read_to_string(&log)?,
+        "hyprctl reload core_desktop ~/.config/hypr\n"
+    );
+
+    // A single file picked inside a bundle directory still counts as that bundle's change.
+    fs::write(&log, "")?;
+    fs::write(&hypr_conf, "monitor = HDMI-A-1\n")?;
+    base_command(&home)
+        .env("PATH", &path)
+        .args([
+            "restore",
+            "--no-ui",
+            "--path",
+            "~/.config/hypr/hyprland.conf",
+        ])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&log)?,
+        "hyprctl reload core_desktop ~/.config/hypr/hyprland.conf\n"
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn schedule_writes_and_removes_systemd_units() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+    let stubs = temp.path().join("stubs");
+    fs::create_dir_all(&stubs)?;
+    let log = temp.path().join("systemctl.log");
+    write_stub(
+        &stubs,
+        "systemctl",
+        &format!(
+            "echo \"$*\" >> '{}'\ncase \"$*\" in *is-active*) echo active ;; esac\n",
+            path_str(&log)?
+        ),
+    )?;
+    let path = path_with(&stubs)?;
+
+    base_command(&home)
+        .env("PATH", &path)
+        .args(["schedule", "enable", "--every", "30m"])
+        .assert()
+        .success();
+
+    let unit_dir = home.join(".config/systemd/user");
+    let service = fs::read_to_string(unit_dir.join("omarchy-syncd-backup.service"))?;
+    let timer = fs::read_to_string(unit_dir.join("omarchy-syncd-backup.timer"))?;
+    assert!(service.contains("backup --all --no-ui"));
+    assert!(timer.contains("OnUnitActiveSec=30m"));
+    let calls = fs::read_to_string(&log)?;
+    assert!(calls.contains("--user daemon-reload"));
+    assert!(calls.contains("--user enable --now omarchy-syncd-backup.timer"));
+
+    let status = base_command(&home)
+        .env("PATH", &path)
+        .args(["schedule", "status"])
+        .output()
+        .context("failed to run schedule status")?;
+    assert!(status.status.success());
+    let stdout = String::from_utf8(status.stdout)?;
+    assert!(stdout.contains("Scheduled backups every 30m."), "{stdout}");
+    assert!(stdout.contains("Timer: active"), "{stdout}");
+
+    base_command(&home)
+        .env("PATH", &path)
+        .args(["schedule", "disable"])
+        .assert()
+        .success();
+    assert!(!unit_dir.join("omarchy-syncd-backup.service").exists());
+    assert!(!unit_dir.join("omarchy-syncd-backup.timer").exists());
+    assert!(fs::read_to_string(&log)?.contains("--user disable --now omarchy-syncd-backup.timer"));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn notifications_follow_backup_outcome() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+    let stubs = temp.path().join("stubs");
+    fs::create_dir_all(&stubs)?;
+    let log = temp.path().join("notify.log");
+    write_stub(
+        &stubs,
+        "notify-send",
+        &format!(
+            "printf '%s|' \"$@\" >> '{}'\necho >> '{}'\n",
+            path_str(&log)?,
+            path_str(&log)?
+        ),
+    )?;
+    let path = path_with(&stubs)?;
+
+    let remote = init_remote_repo(temp.path(), "remote-notify.git")?;
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+    fs::write(&hypr_conf, "monitor = DP-1\n")?;
+
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    let write_config = |url: &str| -> Result<()> {
+        fs::write(
+            config_dir.join("config.toml"),
+            format!(
+                "[repo]\nurl = \"{url}\"\n\n[files]\npaths = []\nbundles = [\"core_desktop\"]\n\n[notifications]\non_success = true\non_no_changes = true\n"
+            ),
+        )?;
+        Ok(())
+    };
+
+    write_config(path_str(&remote)?)?;
+    for _ in 0..2 {
+        base_command(&home)
+            .env("PATH", &path)
+            .args(["backup", "--no-ui"])
+            .assert()
+            .success();
+    }
+
+    let missing = temp.path().join("missing.git");
+    write_config(path_str(&missing)?)?;
+    base_command(&home)
+        .env("PATH", &path)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .failure();
+
+    let log = fs::read_to_string(&log)?;
+    let sent: Vec<&str> = log.split("--app-name|").skip(1).collect();
+    assert_eq!(sent.len(), 3, "notifications: {log}");
+    assert!(sent[0].contains("Backup complete|Changed: core_desktop|"));
+    assert!(sent[1].contains("Backup complete|Nothing changed.|"));
+    assert!(sent[2].contains("critical|Backup failed|"));
+    assert!(
+        sent[2].contains("hint: Check `repo.url`"),
+        "notifications: {log}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn json_output_reports_backup_and_errors() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+
+    let remote = init_remote_repo(temp.path(), "remote-json.git")?;
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+    fs::write(&hypr_conf, "monitor = DP-1\n")?;
+
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    let write_config = |url: &str| -> Result<()> {
+        fs::write(
+            config_dir.join("config.toml"),
+            format!(
+                "[repo]\nurl = \"{url}\"\n\n[files]\npaths = [\"~/.config/hypr\", \"~/.config/missing\"]\nbundles = []\n"
+            ),
+        )?;
+        Ok(())
+    };
+    write_config(path_str(&remote)?)?;
+
+    let run_json = |args: &[&str]| -> Result<(bool, serde_json::Value)> {
+        let output = base_command(&home)
+            .args(args)
+            .args(["--output", "json"])
+            .output()
+            .context("failed to run command with --output json")?;
+        let report = serde_json::from_slice(&output.stdout).with_context(|| {
+            format!(
+                "stdout was not JSON: {}",
+                String::from_utf8_lossy(&output.stdout)
+            )
+        })?;
+        Ok((output.status.success(), report))
+    };
+
+    let (success, report) = run_json(&["backup", "--no-ui"])?;
+    assert!(success);
+    assert_eq!(report["ok"], true);
+    assert_eq!(
+        report["paths"],
+        serde_json::json!(["~/.config/hypr", "~/.config/missing"])
+    );
+    assert_eq!(
+        report["changed_paths"],
+        serde_json::json!(["~/.config/hypr"])
+    );
+    assert_eq!(report["skipped"][0]["path"], "~/.config/missing");
+    assert_eq!(
+        report["skipped"][0]["reason"],
+        "it does not exist on this machine"
+    );
+    assert_eq!(report["changed"], true);
+    assert_eq!(report["pushed"], 1);
+    assert_eq!(report["commit"].as_str().map(str::len), Some(40));
+
+    let (success, report) = run_json(&["backup", "--no-ui"])?;
+    assert!(success);
+    assert_eq!(report["changed"], false);
+    assert_eq!(report["pushed"], 0);
+
+    write_config(path_str(&temp.path().join("missing.git"))?)?;
+    let (success, report) = run_json(&["backup", "--no-ui"])?;
+    assert!(!success);
+    assert_eq!(report["ok"], false);
+    assert_eq!(report["kind"], "repo_not_found");
+    assert!(
+        report["hint"]
+            .as_str()
+            .is_some_and(|hint| hint.contains("repo.url"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn waybar_reports_cached_sync_state() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+
+    let remote = init_remote_repo(temp.path(), "remote-waybar.git")?;
+    let hypr_dir = home.join(".config/hypr");
+    fs::create_dir_all(&hypr_dir)?;
+    fs::write(hypr_dir.join("hyprland.conf"), "monitor = DP-1\n")?;
+
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            "[repo]\nurl = \"{}\"\n\n[files]\npaths = [\"~/.config/hypr\"]\nbundles = []\n",
+            path_str(&remote)?
+        ),
+    )?;
+
+    let waybar = || -> Result<serde_json::Value> {
+        let output = base_command(&home)
+            .arg("waybar")
+            .output()
+            .context("failed to run waybar")?;
+        assert!(output.status.success());
+        Ok(serde_json::from_slice(&output.stdout)?)
+    };
+
+    assert_eq!(waybar()?["class"], "never");
+
+    base_command(&home)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+    let module = waybar()?;
+    assert_eq!(module["class"], "synced");
+    assert_eq!(module["text"], "<1m");
+    assert!(
+        module["tooltip"]
+            .as_str()
+            .unwrap()
+            .contains("Last backup: <1m ago")
+    );
+
+    fs::write(hypr_dir.join("monitors.conf"), "monitor = HDMI-A-1\n")?;
+    let module = waybar()?;
+    assert_eq!(module["class"], "modified");
+    assert_eq!(module["text"], "<1m *");
+
+    base_command(&home)
+        .args(["restore", "--no-ui"])
+        .assert()
+        .success();
+    base_command(&home)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+    assert_eq!(waybar()?["class"], "synced");
+
+    Ok(())
+}
+
+#[test]
+fn doctor_reports_checklist_and_fails_on_problems() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+
+    let remote = init_remote_repo(temp.path(), "remote-doctor.git")?;
+    let hypr_dir = home.join(".config/hypr");
+    fs::create_dir_all(&hypr_dir)?;
+    fs::write(hypr_dir.join("hyprland.conf"), "monitor = DP-1\n")?;
+
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    let config_path = config_dir.join("config.toml");
+    let remote_url = path_str(&remote)?;
+    fs::write(
+        &config_path,
+        format!(
+            "[repo]\nurl = \"{remote_url}\"\n\n[files]\npaths = [\"~/.config/hypr\"]\nbundles = []\n"
+        ),
+    )?;
+    base_command(&home)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+
+    let output = base_command(&home)
+        .arg("doctor")
+        .output()
+        .context("failed to run doctor")?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(output.status.success(), "doctor failed:\n{stdout}");
+    assert!(stdout.contains("[pass] remote:"));
+    assert!(stdout.contains("[pass] paths:"));
+    assert!(stdout.contains("[warn] terminal:"));
+
+    fs::write(
+        &config_path,
+        format!(
+            "[repo]\nurl = \"{remote_url}\"\n\n[files]\npaths = [\"~/.config/hypr\", \"~/.config/missing\"]\nbundles = [\"nope\"]\n"
+        ),
+    )?;
+    let output = base_command(&home)
+        .args(["--output", "json", "doctor"])
+        .output()
+        .context("failed to run doctor")?;
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["ok"], false);
+    let status_of = |name: &str| {
+        report["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|check| check["name"] == name)
+            .map(|check| check["status"].clone())
+    };
+    assert_eq!(status_of("bundles"), Some("fail".into()));
+    assert_eq!(status_of("paths"), Some("warn".into()));
+    assert_eq!(status_of("config"), Some("fail".into()));
+    assert_eq!(report["failures"], 2);
+
+    fs::write(&config_path, "[files\npaths = []\n")?;
+    let output = base_command(&home)
+        .arg("doctor")
+        .output()
+        .context("failed to run doctor")?;
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("[fail] config:"));
+    assert!(stdout.contains("line 1"));
+    Ok(())
+}
+
+#[test]
+fn config_validate_reports_every_problem_with_locations() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    fs::write(
+        config_dir.join("config.toml"),
+        "[repo]\nurl = \"git@example.com:me/dotfiles.git\"\nbrnach = \"main\"\n\n[files]\npaths = [\"~/.config/nvim\", \"/etc/hosts\", \"~/.config/nvim\"]\nbundles = [\"editors\", \"core_desktp\"]\n\n[storage]\nkeep = \"ten\"\n",
+    )?;
+
+    let output = base_command(&home)
+        .args(["config", "validate"])
+        .output()
+        .context("failed to run config validate")?;
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout)?;
+    for expected in [
+        "config.toml:3:1: error: unknown key `brnach` in [repo]; did you mean `branch`?",
+        "config.toml:6:10: warning: `~/.config/nvim` is already tracked by bundle `editors`",
+        "config.toml:6:28: error: Configured path /etc/hosts must live under",
+        "config.toml:6:42: warning: `~/.config/nvim` is listed more than once",
+        "config.toml:7:23: error: unknown bundle `core_desktp`; did you mean `core_desktop`?",
+        "config.toml:10:8: error: invalid type: string \"ten\"",
+    ] {
+        assert!(
+            stdout.contains(expected),
+            "missing {expected:?} in:\n{stdout}"
+        );
+    }
+    assert!(String::from_utf8(output.stderr)?.contains("config.toml has errors."));
+
+    let output = base_command(&home)
+        .args(["--output", "json", "config", "validate"])
+        .output()
+        .context("failed to run config validate")?;
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["ok"], false);
+    assert_eq!(report["problems"].as_array().map(Vec::len), Some(6));
+
+    let output = base_command(&home)
+        .arg("status")
+        .output()
+        .context("failed to run status")?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("line 3, column 1: error: unknown key `brnach`"));
+    assert!(stderr.contains("line 7, column 23: error: unknown bundle"));
+    Ok(())
+}
+
+#[test]
+fn config_migrate_upgrades_unversioned_files() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    let config_path = config_dir.join("config.toml");
+    // Only the version is added; settings the user spelled out, old defaults included, stay.
+    let legacy = "# my dotfiles\n[repo]\nurl = \"git@example.com:me/dotfiles.git\"\n\n[files]\npaths = [\"~/.config/hypr\"]\n\n[hooks]\npost_restore = [\"hyprctl reload\"]\n";
+    fs::write(&config_path, legacy)?;
+
+    let output = base_command(&home)
+        .args(["config", "migrate", "--dry-run"])
+        .output()
+        .context("failed to run config migrate")?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("(version 0)"));
+    assert!(stdout.contains("+version = 1"));
+    assert_eq!(fs::read_to_string(&config_path)?, legacy);
+
+    base_command(&home)
+        .args(["config", "migrate"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&config_path)?,
+        format!("version = 1\n{legacy}")
+    );
+    assert_eq!(
+        fs::read_to_string(config_dir.join("config.toml.bak"))?,
+        legacy
+    );
+
+    fs::write(&config_path, format!("version = 99\n{legacy}"))?;
+    let output = base_command(&home)
+        .arg("status")
+        .output()
+        .context("failed to run status")?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("Config version 99 is newer than this omarchy-syncd supports"));
+    Ok(())
+}
+
+#[test]
+fn loading_an_old_config_does_not_rewrite_it() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    let config_path = config_dir.join("config.toml");
+    let legacy = "[repo]\nurl = \"git@example.com:me/dotfiles.git\"\n\n[files]\npaths = [\"~/.config/hypr\"]\n";
+    fs::write(&config_path, legacy)?;
+
+    for args in [
+        &["status"][..],
+        &["config", "show", "--effective"],
+        &["waybar"],
+    ] {
+        let output = base_command(&home)
+            .args(args)
+            .output()
+            .context("failed to run read-only command")?;
+        assert!(output.status.success(), "{args:?}: {output:?}");
+    }
+    assert_eq!(fs::read_to_string(&config_path)?, legacy);
+    assert!(!config_dir.join("config.toml.bak").exists());
+    Ok(())
+}
+
+#[test]
+fn config_edit_commands_preserve_comments() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    let config_path = config_dir.join("config.toml");
+    fs::write(
+        &config_path,
+        "version = 1\n\n# where snapshots go\n[repo]\nurl = \"git@example.com:old.git\" # remote\n\n[files]\npaths = [\n  \"~/.config/hypr\", # compositor\n]\nbundles = [\"editors\"]\n",
+    )?;
+
+    let get = |key: &str| -> Result<String> {
+        let output = base_command(&home)
+            .args(["config", "get", key])
+            .output()
+            .context("failed to run config get")?;
+        assert!(output.status.success());
+        Ok(String::from_utf8(output.stdout)?)
+    };
+    assert_eq!(get("repo.branch")?, "main\n");
+
+    for args in [
+        vec!["set", "repo.url", "git@example.com:new.git"],
+        vec!["set", "repo.url", "git@example.com:new.git"],
+        vec!["set", "storage.keep", "3"],
+        vec!["add-path", "~/.config/kitty"],
+        vec!["add-path", path_str(&home.join(".config/kitty"))?],
+        vec!["add-bundle", "terminals"],
+        vec!["remove-bundle", "editors"],
+    ] {
+        base_command(&home)
+            .arg("config")
+            .args(&args)
+            .assert()
+            .success();
+    }
+    let edited = "version = 1\n\n# where snapshots go\n[repo]\nurl = \"git@example.com:new.git\" # remote\n\n[files]\npaths = [\n  \"~/.config/hypr\", # compositor\n  \"~/.config/kitty\",\n]\nbundles = [\"terminals\"]\n\n[storage]\nkeep = 3\n";
+    assert_eq!(fs::read_to_string(&config_path)?, edited);
+    assert_eq!(get("repo.url")?, "git@example.com:new.git\n");
+    assert_eq!(get("files.paths")?, "~/.config/hypr\n~/.config/kitty\n");
+
+    let output = base_command(&home)
+        .args(["config", "add-bundle", "nope"])
+        .output()
+        .context("failed to run config add-bundle")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)?.contains("unknown bundle `nope`"));
+    assert_eq!(fs::read_to_string(&config_path)?, edited);
+    Ok(())
+}
+
+#[test]
+fn config_overrides_come_from_flag_and_environment() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let hypr_dir = home.join(".config/hypr");
+    fs::create_dir_all(&hypr_dir)?;
+    fs::write(hypr_dir.join("hyprland.conf"), "monitor = DP-1\n")?;
+
+    let remote = init_remote_repo(temp.path(), "remote-override.git")?;
+    run_git(
+        None,
+        &["--git-dir", path_str(&remote)?, "branch", "ci", "main"],
+    )?;
+    let alt_config = temp.path().join("ci.toml");
+    fs::write(
+        &alt_config,
+        "version = 1\n\n[repo]\nurl = \"git@example.com:unused.git\"\n\n[files]\npaths = [\"~/.config/hypr\"]\n",
+    )?;
+
+    let output = base_command(&home)
+        .env("OMARCHY_SYNCD_REPO_URL", path_str(&remote)?)
+        .env("OMARCHY_SYNCD_BRANCH", "ci")
+        .args(["--config", path_str(&alt_config)?, "backup", "--no-ui"])
+        .output()
+        .context("failed to run backup with overrides")?;
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)?.contains("Skipping config copy"));
+    assert!(!home.join(".config/omarchy-syncd").exists());
+    let log = StdCommand::new("git")
+        .args(["--git-dir", path_str(&remote)?, "log", "--format=%s", "ci"])
+        .output()?;
+    assert!(String::from_utf8(log.stdout)?.contains("Automated backup"));
+    // The CI config and its overridden URL stay out of the snapshot.
+    let tree = StdCommand::new("git")
+        .args([
+            "--git-dir",
+            path_str(&remote)?,
+            "ls-tree",
+            "-r",
+            "--name-only",
+            "ci",
+        ])
+        .output()?;
+    assert!(!String::from_utf8(tree.stdout)?.contains("omarchy-syncd/config.toml"));
+
+    let output = base_command(&home)
+        .env("OMARCHY_SYNCD_CONFIG", path_str(&alt_config)?)
+        .env("OMARCHY_SYNCD_BRANCH", "ci")
+        .args(["config", "show", "--effective"])
+        .output()
+        .context("failed to run config show")?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("(from OMARCHY_SYNCD_CONFIG)"));
+    assert!(stdout.contains("# repo.branch = ci (from OMARCHY_SYNCD_BRANCH)"));
+    assert!(stdout.contains("branch = \"ci\""));
+    assert!(stdout.contains("url = \"git@example.com:unused.git\""));
+    Ok(())
+}
+
+#[test]
+fn bootstrap_fetches_config_from_repo_and_restores() -> Result<()> {
+    let temp = tempdir()?;
+    let remote = init_remote_repo(temp.path(), "remote-bootstrap.git")?;
+    let remote_url = path_str(&remote)?;
+
+    let old_home = temp.path().join("old");
+    let hypr_dir = old_home.join(".config/hypr");
+    fs::create_dir_all(&hypr_dir)?;
+    fs::write(hypr_dir.join("hyprland.conf"), "monitor = DP-1\n")?;
+    let old_config = old_home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&old_config)?;
+    fs::write(
+        old_config.join("config.toml"),
+        format!(
+            "version = 1\n\n# synced from my laptop\n[repo]\nurl = \"{remote_url}\"\n\n[files]\npaths = [\"~/.config/hypr\"]\n"
+        ),
+    )?;
+    base_command(&old_home)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+
+    let new_home = temp.path().join("new");
+    fs::create_dir_all(&new_home)?;
+    base_command(&new_home)
+        .args(["bootstrap", remote_url, "--yes", "--all"])
+        .assert()
+        .success();
+    let written = fs::read_to_string(new_home.join(".config/omarchy-syncd/config.toml"))?;
+    assert!(written.contains("# synced from my laptop"));
+    assert!(written.contains(&format!("url = \"{remote_url}\"")));
+    assert_eq!(
+        fs::read_to_string(new_home.join(".config/hypr/hyprland.conf"))?,
+        "monitor = DP-1\n"
+    );
+
+    let output = base_command(&new_home)
+        .args(["bootstrap", remote_url, "--yes"])
+        .output()
+        .context("failed to rerun bootstrap")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)?.contains("Config already exists"));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn import_maps_chezmoi_source_into_snapshot() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+    let storage_dir = temp.path().join("usb-drive");
+    fs::create_dir_all(&storage_dir)?;
+    let config_dir = home.join(".config/omarchy-syncd");
+    fs::create_dir_all(&config_dir)?;
+    fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            "version = 1\n\n[files]\npaths = []\n\n[storage]\nkind = \"directory\"\npath = \"{}\"\n",
+            path_str(&storage_dir)?
+        ),
+    )?;
+
+    let source = temp.path().join("chezmoi");
+    for (rel, contents) in [
+        ("dot_config/alacritty/alacritty.toml", "[font]\n"),
+        ("dot_config/ghostty/config", "theme = dark\n"),
+        ("dot_config/kitty/kitty.conf", "font_size 11\n"),
+        ("private_dot_ssh/private_config", "Host *\n"),
+        ("dot_local/bin/executable_greet", "#!/bin/sh\necho hi\n"),
+        ("dot_gitconfig.tmpl", "[user]\n  name = {{ .name }}\n"),
+        (".chezmoiignore", "README.md\n"),
+    ] {
+        let path = source.join(rel);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, contents)?;
+    }
+
+    let output = base_command(&home)
+        .args(["--output", "json", "import", "--from", "chezmoi"])
+        .arg(&source)
+        .output()
+        .context("failed to run import")?;
+    assert!(output.status.success(), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["bundles"], serde_json::json!(["terminals"]));
+    assert_eq!(
+        report["paths"],
+        serde_json::json!(["~/.local/bin/greet", "~/.ssh/config"])
+    );
+    assert_eq!(report["skipped"][0]["path"], "~/.gitconfig");
+
+    let written = fs::read_to_string(config_dir.join("config.toml"))?;
+    assert!(written.contains("bundles = [\"terminals\"]"));
+    assert!(written.contains("\"~/.ssh/config\""));
+    assert_eq!(
+        fs::read_to_string(storage_dir.join(".config/kitty/kitty.conf"))?,
+        "font_size 11\n"
+    );
+    assert!(!storage_dir.join(".chezmoiignore").exists());
+
+    base_command(&home)
+        .args(["restore", "--no-ui"])
+        .assert()
+        .success();
+    let mode = |rel: &str| -> Result<u32> {
+        Ok(fs::metadata(home.join(rel))?.permissions().mode() & 0o777)
+    };
+    assert_eq!(mode(".ssh/config")?, 0o600);
+    assert_eq!(mode(".local/bin/greet")?, 0o755);
+    assert_eq!(
+        fs::read_to_string(home.join(".config/ghostty/config"))?,
+        "theme = dark\n"
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn link_mode_restores_symlinks_into_clone_and_backs_up_edits() -> Result<()> {
+    let temp = tempdir()?;
+    let remote = init_remote_repo(temp.path(), "remote-link.git")?;
+    let remote_url = path_str(&remote)?;
+    let config = format!(
+        "version = 1\n\n[repo]\nurl = \"{remote_url}\"\n\n[files]\npaths = [\"~/.config/hypr\", \"~/.zshrc\"]\nmode = \"link\"\n\n[files.modes]\n\"~/.zshrc\" = \"copy\"\n"
+    );
+
+    let laptop = temp.path().join("laptop");
+    let hypr_dir = laptop.join(".config/hypr");
+    fs::create_dir_all(&hypr_dir)?;
+    fs::write(hypr_dir.join("hyprland.conf"), "monitor = DP-1\n")?;
+    symlink("/usr/share/themes/dark", hypr_dir.join("theme"))?;
+    fs::write(laptop.join(".zshrc"), "export EDITOR=nvim\n")?;
+    fs::create_dir_all(laptop.join(".config/omarchy-syncd"))?;
+    fs::write(laptop.join(".config/omarchy-syncd/config.toml"), &config)?;
+
+    base_command(&laptop)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+    let clone = laptop.join(".local/share/omarchy-syncd/repo");
+    let hypr_conf = hypr_dir.join("hyprland.conf");
+    assert_eq!(
+        fs::read_link(&hypr_conf)?,
+        clone.join(".config/hypr/hyprland.conf")
+    );
+    assert!(!laptop.join(".zshrc").is_symlink());
+
+    // Edits land in the clone through the link and are committed by the next backup.
+    fs::write(&hypr_conf, "monitor = DP-2\n")?;
+    let output = base_command(&laptop)
+        .args(["--output", "json", "backup", "--no-ui"])
+        .output()
+        .context("failed to run backup")?;
+    assert!(output.status.success(), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        report["changed_paths"],
+        serde_json::json!(["~/.config/hypr"])
+    );
+    let pushed = StdCommand::new("git")
+        .args([
+            "--git-dir",
+            remote_url,
+            "show",
+            "main:.config/hypr/hyprland.conf",
+        ])
+        .output()?;
+    assert_eq!(String::from_utf8(pushed.stdout)?, "monitor = DP-2\n");
+
+    let desktop = temp.path().join("desktop");
+    fs::create_dir_all(desktop.join(".config/omarchy-syncd"))?;
+    fs::write(desktop.join(".config/omarchy-syncd/config.toml"), &config)?;
+    base_command(&desktop)
+        .args(["restore", "--no-ui"])
+        .assert()
+        .success();
+    let restored = desktop.join(".config/hypr");
+    assert!(restored.join("hyprland.conf").is_symlink());
+    assert!(!restored.is_symlink());
+    assert_eq!(
+        fs::read_to_string(restored.join("hyprland.conf"))?,
+        "monitor = DP-2\n"
+    );
+    assert_eq!(
+        fs::read_link(restored.join("theme"))?,
+        PathBuf::from("/usr/share/themes/dark")
+    );
+    assert_eq!(
+        fs::read_to_string(desktop.join(".zshrc"))?,
+        "export EDITOR=nvim\n"
+    );
+    assert!(!desktop.join(".zshrc").is_symlink());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn leaving_link_mode_turns_links_back_into_copies() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let remote = init_remote_repo(temp.path(), "remote-unlink.git")?;
+    let remote_url = path_str(&remote)?;
+    let config_path = home.join(".config/omarchy-syncd/config.toml");
+    let config = |mode: &str| {
+        format!(
+            "version = 1\n\n[repo]\nurl = \"{remote_url}\"\n\n[files]\npaths = [\"~/.config/hypr\"]\nmode = \"{mode}\"\n"
+        )
+    };
+    let hypr_conf = home.join(".config/hypr/hyprland.conf");
+    fs::create_dir_all(hypr_conf.parent().unwrap())?;
+    fs::write(&hypr_conf, "monitor = DP-1\n")?;
+    fs::create_dir_all(config_path.parent().unwrap())?;
+    fs::write(&config_path, config("link"))?;
+    base_command(&home)
+        .args(["backup", "--no-ui"])
+        .assert()
+        .success();
+    assert!(hypr_conf.is_symlink());
+
+    // The edit only lives in the clone until the next backup commits it.
+    fs::write(&hypr_conf, "monitor = DP-2\n")?;
+    fs::write(&config_path, config("copy"))?;
+    let output = base_command(&home)
+        .args(["backup", "--no-ui"])
+        .output()
+        .context("failed to run backup")?;
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8(output.stdout)?
+            .contains("Replaced links into the local clone with copies")
+    );
+    assert!(!hypr_conf.is_symlink());
+    assert_eq!(fs::read_to_string(&hypr_conf)?, "monitor = DP-2\n");
+    let pushed = StdCommand::new("git")
+        .args([
+            "--git-dir",
+            remote_url,
+            "show",
+            "main:.config/hypr/hyprland.conf",
+        ])
+        .output()?;
+    assert_eq!(String::from_utf8(pushed.stdout)?, "monitor = DP-2\n");
+    Ok(())
+}
+
+#[test]
+fn path_flag_accepts_files_inside_tracked_directories() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+    let remote = init_remote_repo(temp.path(), "remote-sub-paths.git")?;
+
+    let hypr_dir = home.join(".config/hypr");
+    fs::create_dir_all(&hypr_dir)?;
+    fs::write(hypr_dir.join("hyprland.conf"), "monitor = DP-1\n")?;
+    fs::write(
+        hypr_dir.join("bindings.conf"),
+        "bind = SUPER, Q, killactive\n",
+    )?;
+
+    base_command(&home)
+        .args([
+            "config",
+            "--write",
+            "--repo-url",
+            path_str(&remote)?,
+            "--path",
+            "~/.config/hypr",
+        ])
+        .assert()
+        .success();
+    base_command(&home)
+        .args(["backup", "--no-ui", "--all"])
+        .assert()
+        .success();
+
+    // Back up a single edited file; its sibling keeps the content from the first backup.
+    fs::write(hypr_dir.join("hyprland.conf"), "monitor = DP-2\n")?;
+    fs::write(
+        hypr_dir.join("bindings.conf"),
+        "bind = SUPER, W, killactive\n",
+    )?;
+    base_command(&home)
+        .args(["backup", "--path", "~/.config/hypr/hyprland.conf"])
+        .assert()
+        .success();
+
+    fs::write(hypr_dir.join("hyprland.conf"), "local edit\n")?;
+    fs::write(hypr_dir.join("bindings.conf"), "local edit\n")?;
+    let output = base_command(&home)
+        .args([
+            "--output",
+            "json",
+            "restore",
+            "--path",
+            "~/.config/hypr/bindings.conf",
+        ])
+        .output()
+        .context("failed to run restore")?;
+    assert!(output.status.success(), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        report["changed_paths"],
+        serde_json::json!(["~/.config/hypr/bindings.conf"])
+    );
+    assert_eq!(
+        fs::read_to_string(hypr_dir.join("bindings.conf"))?,
+        "bind = SUPER, Q, killactive\n"
+    );
+    assert_eq!(
+        fs::read_to_string(hypr_dir.join("hyprland.conf"))?,
+        "local edit\n"
+    );
+
+    base_command(&home)
+        .args(["restore", "--path", "~/.config/hypr/hyprland.conf"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(hypr_dir.join("hyprland.conf"))?,
+        "monitor = DP-2\n"
+    );
+
+    for outside in ["~/.config/hyprx", "~/.config/hypr/../nvim"] {
+        let output = base_command(&home)
+            .args(["restore", "--path", outside])
+            .output()
+            .context("failed to run restore")?;
+        assert!(!output.status.success());
+        assert!(
+            String::from_utf8(output.stderr)?.contains("not part of the configured backup set")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn dmenu_selector_picks_paths_and_remembers_them() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    for app in ["hypr", "kitty"] {
+        let dir = home.join(".config").join(app);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(format!("{app}.conf")), "# settings\n")?;
+    }
+    let remote = init_remote_repo(temp.path(), "remote-dmenu.git")?;
+
+    // Scripted launcher: toggles kitty on its first run, then picks "Done" (the first line).
+    let bin = temp.path().join("bin");
+    fs::create_dir_all(&bin)?;
+    let log = temp.path().join("dmenu.log");
+    let toggled = temp.path().join("toggled");
+    write_stub(
+        &bin,
+        "pick",
+        &format!(
+            "input=$(cat)\nprintf '%s\\n---\\n' \"$input\" >> '{log}'\nif [ -e '{toggled}' ]; then\n  printf '%s\\n' \"$input\" | head -n 1\nelse\n  touch '{toggled}'\n  printf '%s\\n' \"$input\" | grep kitty\nfi\n",
+            log = path_str(&log)?,
+            toggled = path_str(&toggled)?,
+        ),
+    )?;
+
+    base_command(&home)
+        .args([
+            "config",
+            "--write",
+            "--repo-url",
+            path_str(&remote)?,
+            "--path",
+            "~/.config/hypr",
+            "--path",
+            "~/.config/kitty",
+        ])
+        .assert()
+        .success();
+    base_command(&home)
+        .args(["config", "set", "ui.dmenu_command"])
+        .arg(bin.join("pick"))
+        .assert()
+        .success();
+
+    let output = base_command(&home)
+        .args(["--output", "json", "--selector", "dmenu", "backup"])
+        .output()
+        .context("failed to run backup")?;
+    assert!(output.status.success(), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["paths"], serde_json::json!(["~/.config/kitty"]));
+
+    // The next run opens with kitty already marked, so picking "Done" keeps it.
+    let output = base_command(&home)
+        .env("OMARCHY_SYNCD_SELECTOR", "dmenu")
+        .args(["--output", "json", "backup"])
+        .output()
+        .context("failed to run backup")?;
+    assert!(output.status.success(), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["paths"], serde_json::json!(["~/.config/kitty"]));
+
+    let shown = fs::read_to_string(&log)?;
+    let runs: Vec<&str> = shown.split("---\n").collect();
+    assert!(runs[0].starts_with("✓ Done (0 selected)\n"));
+    assert!(runs[0].contains("[ ] ~/.config/hypr\n[ ] ~/.config/kitty\n"));
+    assert!(runs[1].starts_with("✓ Done (1 selected)\n"));
+    assert!(runs[2].contains("[ ] ~/.config/hypr\n[x] ~/.config/kitty\n"));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn empty_selection_asks_before_using_every_path() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    for app in ["hypr", "kitty"] {
+        let dir = home.join(".config").join(app);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(format!("{app}.conf")), "# settings\n")?;
+    }
+    let remote = init_remote_repo(temp.path(), "remote-empty-pick.git")?;
+
+    // Scripted launcher: picks "Done" with nothing toggled, then answers the confirmation
+    // with $ANSWER.
+    let bin = temp.path().join("bin");
+    fs::create_dir_all(&bin)?;
+    write_stub(
+        &bin,
+        "pick",
+        "input=$(cat)\ncase \"$input\" in\n  Yes*) echo \"$ANSWER\" ;;\n  *) printf '%s\\n' \"$input\" | head -n 1 ;;\nesac\n",
+    )?;
+    let notified = temp.path().join("notify.log");
+    write_stub(
+        &bin,
+        "notify-send",
+        &format!("echo \"$*\" >> '{}'\n", path_str(&notified)?),
+    )?;
+    base_command(&home)
+        .args([
+            "config",
+            "--write",
+            "--repo-url",
+            path_str(&remote)?,
+            "--path",
+            "~/.config/hypr",
+            "--path",
+            "~/.config/kitty",
+        ])
+        .assert()
+        .success();
+    base_command(&home)
+        .args(["config", "set", "ui.dmenu_command"])
+        .arg(bin.join("pick"))
+        .assert()
+        .success();
+
+    let declined = base_command(&home)
+        .env("PATH", path_with(&bin)?)
+        .env("ANSWER", "No")
+        .args(["--selector", "dmenu", "backup"])
+        .output()
+        .context("failed to run backup")?;
+    assert!(!declined.status.success());
+    assert!(String::from_utf8(declined.stderr)?.contains("Selection cancelled: no paths selected"));
+    // Backing out is not a failure: no notification, and Waybar shows no error.
+    assert!(!notified.exists(), "cancelling should not notify");
+    let waybar = base_command(&home)
+        .arg("waybar")
+        .output()
+        .context("failed to run waybar")?;
+    let module: serde_json::Value = serde_json::from_slice(&waybar.stdout)?;
+    assert_eq!(module["class"], "never");
+
+    let output = base_command(&home)
+        .env("ANSWER", "Yes")
+        .args(["--output", "json", "--selector", "dmenu", "backup"])
+        .output()
+        .context("failed to run backup")?;
+    assert!(output.status.success(), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        report["paths"],
+        serde_json::json!(["~/.config/hypr", "~/.config/kitty"])
+    );
+    Ok(())
+}
+
+#[test]
+fn non_interactive_mode_fails_instead_of_prompting() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    fs::create_dir_all(&home)?;
+    base_command(&home)
+        .args([
+            "config",
+            "--write",
+            "--repo-url",
+            "git@example.com:dotfiles.git",
+            "--path",
+            "~/.config/hypr",
+        ])
+        .assert()
+        .success();
+
+    // The config action menu would otherwise open because no flags were given.
+    let output = base_command(&home)
+        .args(["--non-interactive", "config"])
+        .output()
+        .context("failed to run config")?;
+    assert_eq!(output.status.code(), Some(3), "{output:?}");
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("The Config actions menu needs input"));
+
+    let output = base_command(&home)
+        .env("OMARCHY_SYNCD_NONINTERACTIVE", "1")
+        .args(["--output", "json", "uninstall"])
+        .output()
+        .context("failed to run uninstall")?;
+    assert_eq!(output.status.code(), Some(3), "{output:?}");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["kind"], "input_required");
+    assert!(report["hint"].as_str().unwrap().contains("--yes"));
+    assert!(home.join(".config/omarchy-syncd/config.toml").exists());
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::env;
+use std::fs;
+
+use anyhow::Result;
+use omarchy_syncd::preview::Previewer;
+use tempfile::tempdir;
+
+// The previewer reads `$HOME`, so everything runs in one test to keep the variable stable.
+#[test]
+fn previews_describe_local_and_repo_copies() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let repo = temp.path().join("repo");
+    // SAFETY: this binary runs a single test, so no other thread reads the environment.
+    unsafe {
+        env::set_var("HOME", &home);
+        env::remove_var("XDG_DATA_HOME");
+    }
+
+    let write = |path: std::path::PathBuf, contents: &str| -> Result<()> {
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, contents)?;
+        Ok(())
+    };
+    write(home.join(".zshrc"), "export EDITOR=nvim\n")?;
+    write(repo.join(".bashrc"), "export EDITOR=vim\n")?;
+    write(home.join(".config/hypr/hyprland.conf"), "monitor = DP-2\n")?;
+    write(repo.join(".config/hypr/hyprland.conf"), "monitor = DP-1\n")?;
+    write(home.join(".config/hypr/new.conf"), "bind = SUPER, Q\n")?;
+    write(repo.join(".config/hypr/gone.conf"), "bind = SUPER, W\n")?;
+    write(home.join(".config/waybar/config"), "{}\n")?;
+    write(repo.join(".config/waybar/config"), "{}\n")?;
+
+    let previewer = Previewer::new(Some(repo.clone()))?;
+
+    let local_only = previewer.render("~/.zshrc");
+    assert!(local_only.contains("Local: file, 19 B"), "{local_only}");
+    assert!(local_only.contains("Repo:  missing"), "{local_only}");
+    assert!(local_only.contains("  A .zshrc"), "{local_only}");
+    assert!(local_only.contains("Last backup: never"), "{local_only}");
+
+    let repo_only = previewer.render("~/.bashrc");
+    assert!(repo_only.contains("Local: missing"), "{repo_only}");
+    assert!(repo_only.contains("  D .bashrc"), "{repo_only}");
+
+    let modified = previewer.render("~/.config/hypr");
+    assert!(
+        modified.contains("Local: directory, 2 file(s)"),
+        "{modified}"
+    );
+    assert!(modified.contains("  M hyprland.conf"), "{modified}");
+    assert!(modified.contains("  A new.conf"), "{modified}");
+    assert!(modified.contains("  D gone.conf"), "{modified}");
+    assert!(modified.contains("-monitor = DP-1"), "{modified}");
+    assert!(modified.contains("+monitor = DP-2"), "{modified}");
+
+    let identical = previewer.render("~/.config/waybar");
+    assert!(
+        identical.ends_with("Local and repo copies match."),
+        "{identical}"
+    );
+
+    // Long diffs stop after a fixed number of lines.
+    let old: String = (0..200).map(|n| format!("old {n}\n")).collect();
+    let new: String = (0..200).map(|n| format!("new {n}\n")).collect();
+    write(repo.join(".config/long.conf"), &old)?;
+    write(home.join(".config/long.conf"), &new)?;
+    let truncated = previewer.render("~/.config/long.conf");
+    assert!(truncated.ends_with("\n…"), "{truncated}");
+    assert!(truncated.contains("-old 0"), "{truncated}");
+    assert!(!truncated.contains("-old 199"), "{truncated}");
+
+    let unsynced = Previewer::new(None)?.render("~/.zshrc");
+    assert!(
+        unsynced.contains("Repo:  unknown until the next sync"),
+        "{unsynced}"
+    );
+    Ok(())
+}